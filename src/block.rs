@@ -5,203 +5,1455 @@
 /* crate use */
 
 /* project use */
+use crate::error;
+
+/* mod declaration */
+#[cfg(feature = "bgzf")]
+pub mod bgzf;
+pub mod buffered;
+pub mod memory;
+pub mod reverse;
+pub mod sample;
+
+/// Memory that contains data of a block
+#[derive(Debug, Clone)]
+enum Memory {
+    Mmap(std::sync::Arc<memmap2::Mmap>),
+    Buffer(std::sync::Arc<Vec<u8>>),
+    Bytes(std::sync::Arc<[u8]>),
+    Static(&'static [u8]),
+    Private(std::sync::Arc<memmap2::MmapMut>),
+}
+
+impl std::ops::Deref for Memory {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Memory::Mmap(mem) => mem,
+            Memory::Buffer(mem) => mem,
+            Memory::Bytes(mem) => mem,
+            Memory::Static(mem) => mem,
+            Memory::Private(mem) => mem,
+        }
+    }
+}
 
 /// Block reperesent a section of file memory mapped in file
-#[derive(Debug)]
+///
+/// Memory is shared between clone of a Block
+#[derive(Debug, Clone)]
 pub struct Block {
-    mem: memmap2::Mmap,
+    mem: Memory,
+    start: usize,
     end: usize,
+    source_index: usize,
+    path: Option<std::sync::Arc<std::path::Path>>,
+    offset: u64,
+    index: u64,
+}
+
+impl Block {
+    /// Create a new Block
+    pub fn new(end: usize, mem: memmap2::Mmap) -> Self {
+        Self::from_shared(0..end, std::sync::Arc::new(mem))
+    }
+
+    /// Create a new Block on a range of a shared memory mapping
+    pub fn from_shared(range: std::ops::Range<usize>, mem: std::sync::Arc<memmap2::Mmap>) -> Self {
+        Self::from_memory(range, Memory::Mmap(mem))
+    }
+
+    /// Create a new Block on a range of a shared buffer
+    pub fn from_buffer(range: std::ops::Range<usize>, buffer: std::sync::Arc<Vec<u8>>) -> Self {
+        Self::from_memory(range, Memory::Buffer(buffer))
+    }
+
+    /// Create a new Block on a range of shared bytes
+    pub fn from_bytes(range: std::ops::Range<usize>, bytes: std::sync::Arc<[u8]>) -> Self {
+        Self::from_memory(range, Memory::Bytes(bytes))
+    }
+
+    /// Create a new Block on a range of static bytes, like an embedded reference, without copy
+    pub fn from_static(range: std::ops::Range<usize>, bytes: &'static [u8]) -> Self {
+        Self::from_memory(range, Memory::Static(bytes))
+    }
+
+    /// Create a new Block on a range of a private copy-on-write mapping
+    pub fn from_private(
+        range: std::ops::Range<usize>,
+        mem: std::sync::Arc<memmap2::MmapMut>,
+    ) -> Self {
+        Self::from_memory(range, Memory::Private(mem))
+    }
+
+    fn from_memory(range: std::ops::Range<usize>, mem: Memory) -> Self {
+        Self {
+            mem,
+            start: range.start,
+            end: range.end,
+            source_index: 0,
+            path: None,
+            offset: 0,
+            index: 0,
+        }
+    }
+
+    /// Set index and path of file where block come from
+    pub fn set_source(&mut self, index: usize, path: Option<std::sync::Arc<std::path::Path>>) {
+        self.source_index = index;
+        self.path = path;
+    }
+
+    /// Acces to data owned by block
+    pub fn data(&self) -> &[u8] {
+        &self.mem[self.start..self.end]
+    }
+
+    /// Mutable access to data of block
+    ///
+    /// If block is the only owner of a private mapping data is edited in place, in other case data is copied in a buffer owned by block.
+    pub fn data_mut(&mut self) -> &mut [u8] {
+        let unique = match &self.mem {
+            Memory::Private(mem) => std::sync::Arc::strong_count(mem) == 1,
+            Memory::Buffer(mem) => std::sync::Arc::strong_count(mem) == 1,
+            _ => false,
+        };
+
+        if !unique {
+            let copy = self.data().to_vec();
+            self.start = 0;
+            self.end = copy.len();
+            self.mem = Memory::Buffer(std::sync::Arc::new(copy));
+        }
+
+        let range = self.start..self.end;
+        match &mut self.mem {
+            Memory::Private(mem) => &mut std::sync::Arc::get_mut(mem).unwrap()[range],
+            Memory::Buffer(mem) => &mut std::sync::Arc::get_mut(mem).unwrap()[range],
+            _ => unreachable!(),
+        }
+    }
+
+    /// Get length of block
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Return true if the block is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Set absolute offset of block in file
+    pub fn set_offset(&mut self, offset: u64) {
+        self.offset = offset;
+    }
+
+    /// Set sequence number of block
+    pub fn set_index(&mut self, index: u64) {
+        self.index = index;
+    }
+
+    /// Get absolute offset of block in file
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Get sequence number of block, first block produce by a producer is 0
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    /// Get index of file where block come from
+    pub fn source_index(&self) -> usize {
+        self.source_index
+    }
+
+    /// Get path of file where block come from
+    pub fn path(&self) -> Option<&std::path::Path> {
+        self.path.as_deref()
+    }
+
+    /// Get origin of block
+    pub fn origin(&self) -> Origin<'_> {
+        self.origin_at(0)
+    }
+
+    /// Get origin of data at position in block
+    pub fn origin_at(&self, position: usize) -> Origin<'_> {
+        Origin {
+            source_index: self.source_index,
+            path: self.path(),
+            block_index: self.index,
+            file_offset: self.offset + position as u64,
+        }
+    }
+
+    /// Get line number of position in block, first line of block is 1
+    ///
+    /// Only data of block is read, combine it with [Block::offset] and [Block::index] to find line in input.
+    pub fn line_at(&self, position: usize) -> u64 {
+        count_lines(&self.data()[..position.min(self.len())]) + 1
+    }
+
+    /// Get a block that share memory of this block and cover range of its data
+    pub fn slice(&self, range: std::ops::Range<usize>) -> Block {
+        let end = range.end.min(self.len());
+        let start = range.start.min(end);
+
+        let mut block = self.clone();
+        block.start = self.start + start;
+        block.end = self.start + end;
+        block.offset = self.offset + start as u64;
+
+        block
+    }
+
+    /// Shrink block to length
+    fn truncate(&mut self, length: usize) {
+        self.end = self.start + length.min(self.len());
+    }
+}
+
+impl From<&'static [u8]> for Block {
+    fn from(bytes: &'static [u8]) -> Self {
+        Block::from_static(0..bytes.len(), bytes)
+    }
+}
+
+impl<const N: usize> From<&'static [u8; N]> for Block {
+    fn from(bytes: &'static [u8; N]) -> Self {
+        Block::from_static(0..N, bytes)
+    }
+}
+
+impl From<Vec<u8>> for Block {
+    fn from(buffer: Vec<u8>) -> Self {
+        Block::from_buffer(0..buffer.len(), std::sync::Arc::new(buffer))
+    }
+}
+
+impl From<std::sync::Arc<Vec<u8>>> for Block {
+    fn from(buffer: std::sync::Arc<Vec<u8>>) -> Self {
+        Block::from_buffer(0..buffer.len(), buffer)
+    }
+}
+
+impl From<std::sync::Arc<[u8]>> for Block {
+    fn from(bytes: std::sync::Arc<[u8]>) -> Self {
+        Block::from_bytes(0..bytes.len(), bytes)
+    }
+}
+
+/// Origin of a record
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Origin<'a> {
+    /// Index of file where record come from
+    pub source_index: usize,
+
+    /// Path of file where record come from
+    pub path: Option<&'a std::path::Path>,
+
+    /// Sequence number of block where record come from
+    pub block_index: u64,
+
+    /// Absolute offset of record in file
+    pub file_offset: u64,
+}
+
+/// Progress of a producer, in bytes of input file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// Bytes of input already consumed, for compressed input it's bytes of compressed file
+    pub consumed: u64,
+
+    /// Length of input
+    pub total: u64,
+}
+
+impl Progress {
+    /// Get fraction of input already consumed, 1.0 for an empty input
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.consumed.min(self.total) as f64 / self.total as f64
+        }
+    }
+}
+
+/// How producers and readers handle malformed records
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ParseMode {
+    /// First malformed record stop parsing with an error
+    #[default]
+    Strict,
+
+    /// Malformed records are skipped, reader resync on next valid record and count skipped data in [Skipped]
+    Lenient,
+}
+
+/// Data skipped by readers in [ParseMode::Lenient]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Skipped {
+    /// Number of malformed records skipped
+    pub records: u64,
+
+    /// Number of bytes skipped
+    pub bytes: u64,
+}
+
+impl std::ops::AddAssign for Skipped {
+    fn add_assign(&mut self, other: Self) {
+        self.records += other.records;
+        self.bytes += other.bytes;
+    }
+}
+
+/// Blocksize choose from a sample of records at begin of file
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BlocksizeEstimate {
+    /// Choosen blocksize, a multiple of page size
+    pub blocksize: u64,
+
+    /// Number of records in sample
+    pub records: u64,
+
+    /// Mean length of records in sample
+    pub record_length: f64,
+
+    /// Length of the largest record in sample
+    pub max_record_length: u64,
+
+    /// Mean number of lines by record in sample
+    pub lines_per_record: f64,
+}
+
+/// Options that control memory mapping and kernel hints of a producer
+///
+/// Kernel hints are only apply on unix platform, failure of an hint is logged and ignored.
+#[derive(Debug, Clone, Default)]
+pub struct MapOptions {
+    /// Advise kernel that mapping is read sequentially (`MADV_SEQUENTIAL`)
+    pub sequential: bool,
+
+    /// Ask kernel to read next block ahead of time (`MADV_WILLNEED`, or `POSIX_FADV_WILLNEED` if file isn't map once)
+    pub prefetch: bool,
+
+    /// Advise kernel that pages of consumed blocks could be drop (`MADV_DONTNEED`), only used with map once, in other case mapping of a block is release when block is drop
+    pub drop_consumed: bool,
+
+    /// Populate page table when file is mapped (`MAP_POPULATE`, Linux only)
+    pub populate: bool,
+
+    /// Lock mapping in memory (`mlock`)
+    pub lock: bool,
+
+    /// Map blocks in a private copy-on-write mapping (`MAP_PRIVATE`), edition of block never reach file, only used if file isn't map once
+    pub copy_on_write: bool,
+}
+
+/// State shared by all [BlockProducer]
+#[derive(Debug)]
+pub struct ProducerState {
+    offset: u64,
+    blocksize: u64,
+    max_blocksize: u64,
+    file: std::fs::File,
+    file_length: u64,
+    end: u64,
+    mapping: Option<std::sync::Arc<memmap2::Mmap>>,
+    path: std::sync::Arc<std::path::Path>,
+    source_index: usize,
+    block_index: u64,
+    map_options: MapOptions,
+    released: u64,
+    stream: Option<buffered::Buffer<Box<dyn std::io::Read + Send>>>,
+    compressed: Option<std::sync::Arc<std::sync::atomic::AtomicU64>>,
+    modified: Option<std::time::SystemTime>,
+    allow_append: bool,
+    estimate: Option<BlocksizeEstimate>,
+    parse_mode: ParseMode,
+}
+
+impl ProducerState {
+    /// Create a new producer state
+    pub fn new<P>(blocksize: u64, offset: u64, path: P) -> error::Result<Self>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let file =
+            std::fs::File::open(&path).map_err(|source| error::Error::OpenFile { source })?;
+        let metadata = file
+            .metadata()
+            .map_err(|source| error::Error::MetaDataFile { source })?;
+        let file_length = metadata.len();
+
+        #[allow(unused_mut)]
+        let mut state = Self {
+            offset,
+            blocksize: file_length.min(blocksize),
+            max_blocksize: crate::DEFAULT_MAX_BLOCKSIZE,
+            file,
+            file_length,
+            end: file_length,
+            mapping: None,
+            path: std::sync::Arc::from(path.as_ref()),
+            source_index: 0,
+            block_index: 0,
+            map_options: MapOptions::default(),
+            parse_mode: ParseMode::default(),
+            released: 0,
+            stream: None,
+            compressed: None,
+            modified: metadata.modified().ok(),
+            allow_append: false,
+            estimate: None,
+        };
+
+        #[cfg(feature = "gzip")]
+        if is_gzip(&state.file)? {
+            let (reader, compressed) = gzip_reader(&state.file)?;
+            state.set_stream(blocksize, reader)?;
+            state.compressed = Some(compressed);
+        }
+
+        Ok(state)
+    }
+
+    /// Get progress of producer
+    pub fn progress(&self) -> Progress {
+        let consumed = match &self.compressed {
+            Some(counter) => counter.load(std::sync::atomic::Ordering::Relaxed),
+            None => self.offset,
+        };
+
+        Progress {
+            consumed,
+            total: self.file_length,
+        }
+    }
+
+    /// Read blocks from stream instead of mapping file, offset is now an offset in stream
+    #[cfg(feature = "gzip")]
+    fn set_stream(
+        &mut self,
+        blocksize: u64,
+        reader: Box<dyn std::io::Read + Send>,
+    ) -> error::Result<()> {
+        let mut stream = buffered::Buffer::new(reader);
+        stream.skip(self.offset)?;
+
+        self.blocksize = blocksize;
+        self.offset = stream.offset();
+        self.stream = Some(stream);
+
+        Ok(())
+    }
+
+    /// Get next block of stream
+    fn next_stream_block(
+        &mut self,
+        correct_block_size: fn(&[u8]) -> error::Result<u64>,
+    ) -> error::Result<Option<Block>> {
+        let stream = match self.stream.as_mut() {
+            Some(stream) => stream,
+            None => return Ok(None),
+        };
+
+        let block = stream.next_block(
+            self.blocksize,
+            self.max_blocksize,
+            self.parse_mode,
+            correct_block_size,
+        )?;
+        self.offset = stream.offset();
+
+        Ok(block.map(|mut block| {
+            block.set_source(self.source_index, Some(self.path.clone()));
+            block
+        }))
+    }
+
+    /// Map whole file in memory, after this call each block is a range of this mapping
+    pub fn map_whole_file(&mut self) -> error::Result<()> {
+        if self.stream.is_some() {
+            return Err(error::Error::NotMappable);
+        }
+
+        self.check_unchanged()?;
+        let mem = self.mmap(0, self.file_length)?;
+
+        self.mapping = Some(std::sync::Arc::new(mem));
+
+        Ok(())
+    }
+
+    /// Map length bytes after offset with map options
+    fn mmap(&self, offset: u64, length: u64) -> error::Result<memmap2::Mmap> {
+        let mut options = memmap2::MmapOptions::new();
+        options.offset(offset).len(length as usize);
+        if self.map_options.populate {
+            options.populate();
+        }
+
+        #[allow(unused_mut)]
+        let mut mem = unsafe {
+            options
+                .map(&self.file)
+                .map_err(|source| error::Error::MapFile { source })?
+        };
+
+        #[cfg(unix)]
+        {
+            if self.map_options.sequential {
+                if let Err(e) = mem.advise(memmap2::Advice::Sequential) {
+                    log::debug!("madvise sequential failled {}", e);
+                }
+            }
+
+            if self.map_options.lock {
+                mem.lock()
+                    .map_err(|source| error::Error::MapFile { source })?;
+            }
+        }
+
+        Ok(mem)
+    }
+
+    /// Map length bytes after offset in a private copy-on-write mapping with map options
+    fn mmap_copy(&self, offset: u64, length: u64) -> error::Result<memmap2::MmapMut> {
+        let mut options = memmap2::MmapOptions::new();
+        options.offset(offset).len(length as usize);
+        if self.map_options.populate {
+            options.populate();
+        }
+
+        #[allow(unused_mut)]
+        let mut mem = unsafe {
+            options
+                .map_copy(&self.file)
+                .map_err(|source| error::Error::MapFile { source })?
+        };
+
+        #[cfg(unix)]
+        {
+            if self.map_options.sequential {
+                if let Err(e) = mem.advise(memmap2::Advice::Sequential) {
+                    log::debug!("madvise sequential failled {}", e);
+                }
+            }
+
+            if self.map_options.lock {
+                mem.lock()
+                    .map_err(|source| error::Error::MapFile { source })?;
+            }
+        }
+
+        Ok(mem)
+    }
+
+    /// Build a block that cover length bytes after offset
+    fn map(&self, offset: u64, length: u64) -> error::Result<Block> {
+        if self.stream.is_some() {
+            return Err(error::Error::NotMappable);
+        }
+
+        let mut block = self.map_range(offset, length)?;
+
+        block.set_source(self.source_index, Some(self.path.clone()));
+        block.set_offset(offset);
+
+        Ok(block)
+    }
+
+    /// Check size and modification time of file didn't change since it was opened
+    ///
+    /// Touch a mapping of a truncated file raise a SIGBUS, this check reduce the risk but a change between check and read of block can't be detected. If append is allowed only truncation is detected, producer stop at length of file when it was opened.
+    fn check_unchanged(&self) -> error::Result<()> {
+        let metadata = self
+            .file
+            .metadata()
+            .map_err(|source| error::Error::MetaDataFile { source })?;
+
+        let changed = if self.allow_append {
+            metadata.len() < self.file_length
+        } else {
+            metadata.len() != self.file_length || metadata.modified().ok() != self.modified
+        };
+
+        if changed {
+            return Err(error::Error::FileChanged {
+                path: self.path.to_path_buf(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Take a shared advisory lock on file (`flock`), lock is release when producer is drop
+    ///
+    /// Only processes that also take a lock are blocked, on non unix platform this function do nothing.
+    pub fn lock_file(&self) -> error::Result<()> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::io::AsRawFd as _;
+
+            if unsafe { libc::flock(self.file.as_raw_fd(), libc::LOCK_SH) } != 0 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Give next sequence number to block
+    fn emit(&mut self, mut block: Block) -> Block {
+        block.set_index(self.block_index);
+        self.block_index += 1;
+
+        #[cfg(unix)]
+        self.advise(&block);
+
+        block
+    }
+
+    /// Apply prefetch and drop consumed hints around block
+    #[cfg(unix)]
+    fn advise(&mut self, block: &Block) {
+        let next = block.offset() + block.len() as u64;
+        let length = self.blocksize.min(self.end.saturating_sub(next));
+
+        match &self.mapping {
+            Some(mem) => {
+                if self.map_options.prefetch && length != 0 {
+                    if let Err(e) =
+                        mem.advise_range(memmap2::Advice::WillNeed, next as usize, length as usize)
+                    {
+                        log::debug!("madvise willneed failled {}", e);
+                    }
+                }
+
+                if self.map_options.drop_consumed && self.released < block.offset() {
+                    if let Err(e) = mem.advise_range(
+                        memmap2::Advice::DontNeed,
+                        self.released as usize,
+                        (block.offset() - self.released) as usize,
+                    ) {
+                        log::debug!("madvise dontneed failled {}", e);
+                    }
+                }
+
+                self.released = block.offset();
+            }
+            #[cfg(target_os = "linux")]
+            None if self.map_options.prefetch && length != 0 => {
+                use std::os::unix::io::AsRawFd as _;
+
+                let ret = unsafe {
+                    libc::posix_fadvise(
+                        self.file.as_raw_fd(),
+                        next as libc::off_t,
+                        length as libc::off_t,
+                        libc::POSIX_FADV_WILLNEED,
+                    )
+                };
+                if ret != 0 {
+                    log::debug!("posix_fadvise willneed failled {}", ret);
+                }
+            }
+            None => (),
+        }
+    }
+
+    /// Map or share range of file
+    fn map_range(&self, offset: u64, length: u64) -> error::Result<Block> {
+        self.check_unchanged()?;
+
+        match &self.mapping {
+            Some(mem) => Ok(Block::from_shared(
+                offset as usize..(offset + length) as usize,
+                mem.clone(),
+            )),
+            None if self.map_options.copy_on_write => {
+                let mem = self.mmap_copy(offset, length)?;
+
+                Ok(Block::from_private(0..mem.len(), std::sync::Arc::new(mem)))
+            }
+            None => {
+                let mem = self.mmap(offset, length)?;
+
+                Ok(Block::new(mem.len(), mem))
+            }
+        }
+    }
+}
+
+/// Trait that produce [Block] from a file.
+///
+/// Each block are shrink to remove last partial record, an implementor only have to provide access to a [ProducerState] and a way to find the end of the last complete record of a block.
+pub trait BlockProducer: Sized {
+    /// Build producer from a [ProducerState]
+    fn from_state(state: ProducerState) -> Self;
+
+    /// Get producer state
+    fn state(&self) -> &ProducerState;
+
+    /// Get mutable producer state
+    fn state_mut(&mut self) -> &mut ProducerState;
+
+    /// Search the begin of the partial record at the end of [Block]
+    fn correct_block_size(block: &[u8]) -> error::Result<u64>;
+
+    /// Search the begin of the first record that start at or after `from` in data
+    ///
+    /// Position 0 of data is considered as a line start. Return None if no record begin could be confirmed in data. Default implementation consider each line as a record.
+    fn find_record_start(data: &[u8], from: usize) -> error::Result<Option<usize>> {
+        Ok(line_starts(data, from).next())
+    }
+
+    /// Create a new Block producer
+    #[inline(always)]
+    fn new<P>(path: P) -> error::Result<Self>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        Self::with_blocksize(crate::DEFAULT_BLOCKSIZE, path)
+    }
+
+    /// Create a new Block producer with a blocksize choose by user
+    fn with_blocksize<P>(blocksize: u64, path: P) -> error::Result<Self>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        Self::with_blocksize_offset(blocksize, 0, path)
+    }
+
+    /// Create a new Block producer with offset choose by user
+    fn with_offset<P>(offset: u64, path: P) -> error::Result<Self>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        Self::with_blocksize_offset(crate::DEFAULT_BLOCKSIZE, offset, path)
+    }
+
+    /// Create a new Block producer with blocksize and offset choose by user
+    fn with_blocksize_offset<P>(blocksize: u64, offset: u64, path: P) -> error::Result<Self>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        Ok(Self::from_state(ProducerState::new(
+            blocksize, offset, path,
+        )?))
+    }
+
+    /// Create a new Block producer that read records that begin between start and end
+    ///
+    /// Start and end are moved forward to the next record begin, so ranges that share a limit never read the same record.
+    fn with_range<P>(start: u64, end: u64, path: P) -> error::Result<Self>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        Self::with_blocksize_range(crate::DEFAULT_BLOCKSIZE, start, end, path)
+    }
+
+    /// Create a new Block producer with blocksize choose by user that read records that begin between start and end
+    fn with_blocksize_range<P>(blocksize: u64, start: u64, end: u64, path: P) -> error::Result<Self>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let mut producer = Self::with_blocksize(blocksize, path)?;
+
+        let end = producer.next_record_start(end)?;
+        let start = producer.next_record_start(start.min(end))?;
+
+        producer.set_offset(start);
+        producer.state_mut().end = end;
+
+        Ok(producer)
+    }
+
+    /// Create a new producer, blocksize is choose to contains [DEFAULT_RECORDS_PER_BLOCK](crate::DEFAULT_RECORDS_PER_BLOCK) records
+    fn auto<P>(path: P) -> error::Result<Self>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        Self::auto_with_records(crate::DEFAULT_RECORDS_PER_BLOCK, path)
+    }
+
+    /// Create a new producer, blocksize is choose to contains records records
+    ///
+    /// Records at begin of file are sampled to estimate record length, choice is available with [blocksize_estimate](BlockProducer::blocksize_estimate). Compressed input keep default blocksize.
+    fn auto_with_records<P>(records: u64, path: P) -> error::Result<Self>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let mut producer = Self::new(path)?;
+
+        if producer.state().stream.is_none() && producer.file_length() != 0 {
+            let estimate = producer.estimate_blocksize(records)?;
+
+            log::info!(
+                "Blocksize {} choosen for {}, {} records sampled, mean record length {:.1}, max record length {}, {:.1} lines by record",
+                estimate.blocksize,
+                producer.path().display(),
+                estimate.records,
+                estimate.record_length,
+                estimate.max_record_length,
+                estimate.lines_per_record
+            );
+
+            producer.state_mut().blocksize = estimate.blocksize.min(producer.file_length());
+            producer.state_mut().estimate = Some(estimate);
+        }
+
+        Ok(producer)
+    }
+
+    /// Estimate a blocksize that contains records records from a sample of begin of file
+    fn estimate_blocksize(&self, records: u64) -> error::Result<BlocksizeEstimate> {
+        let begin = self.offset();
+        let available = self.end().saturating_sub(begin);
+        let mut length = SAMPLE_LENGTH.min(available);
+
+        let (window, starts) = loop {
+            let window = self.state().map(begin, length)?;
+
+            let mut starts = Vec::new();
+            let mut from = 0;
+            while let Some(pos) = Self::find_record_start(window.data(), from)? {
+                starts.push(pos);
+                from = pos + 1;
+            }
+
+            if starts.len() > 1 || length >= available || length >= self.max_blocksize() {
+                break (window, starts);
+            }
+            length = (length * 2).min(available).min(self.max_blocksize());
+        };
+
+        let data = window.data();
+        let (begin, end) = match (starts.first(), starts.last()) {
+            (Some(first), Some(last)) if starts.len() > 1 => (*first, *last),
+            _ => (0, data.len()),
+        };
+        let nb_records = (starts.len().max(2) - 1) as u64;
+        let max_record_length = starts
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]) as u64)
+            .max()
+            .unwrap_or((end - begin) as u64);
+
+        let record_length = (end - begin) as f64 / nb_records as f64;
+        let lines = data[begin..end].iter().filter(|c| **c == b'\n').count();
+
+        let target = ((record_length * records as f64) as u64).max(max_record_length * 2);
+        let page = page_size();
+
+        Ok(BlocksizeEstimate {
+            blocksize: ((target + page - 1) / page * page).min(self.max_blocksize()),
+            records: nb_records,
+            record_length,
+            max_record_length,
+            lines_per_record: lines as f64 / nb_records as f64,
+        })
+    }
+
+    /// Get blocksize estimation used by [auto](BlockProducer::auto)
+    fn blocksize_estimate(&self) -> Option<BlocksizeEstimate> {
+        self.state().estimate
+    }
+
+    /// Get file offset of the first record that begin at or after from
+    ///
+    /// If no record begin after from, file length is returned.
+    fn next_record_start(&self, from: u64) -> error::Result<u64> {
+        if from >= self.file_length() {
+            return Ok(self.file_length());
+        }
+
+        let window_start = from.saturating_sub(1);
+        let mut length = self.blocksize().max(1);
+
+        loop {
+            length = length.min(self.file_length() - window_start);
+
+            let window = self.state().map(window_start, length)?;
+
+            match Self::find_record_start(window.data(), (from - window_start) as usize)? {
+                Some(pos) => return Ok(window_start + pos as u64),
+                None if window_start + length >= self.file_length() => {
+                    return Ok(self.file_length())
+                }
+                None if length >= self.max_blocksize() => {
+                    return Err(locate(
+                        error::Error::NoRecordStart,
+                        &window,
+                        (from - window_start) as usize,
+                    ))
+                }
+                None => length = (length * 2).min(self.max_blocksize()),
+            }
+        }
+    }
+
+    /// Get next block
+    ///
+    /// If no complete record fit in blocksize, mapping size is doubled until a record fit or [max_blocksize](BlockProducer::max_blocksize) is reach.
+    fn next_block(&mut self) -> error::Result<Option<Block>> {
+        if self.state().stream.is_some() {
+            return self.state_mut().next_stream_block(Self::correct_block_size);
+        }
+
+        let mut length = self.blocksize();
+
+        loop {
+            if self.offset() >= self.end() {
+                return Ok(None);
+            } else if self.offset() + length >= self.end() {
+                let block = self
+                    .state()
+                    .map(self.offset(), self.end() - self.offset())?;
+
+                self.set_offset(self.end());
+
+                return Ok(Some(self.state_mut().emit(block)));
+            } else {
+                let mut block = self.state().map(self.offset(), length)?;
+                block.set_index(self.state().block_index);
+
+                let cut = cut_block(
+                    block.data(),
+                    self.max_blocksize(),
+                    self.parse_mode(),
+                    Self::correct_block_size,
+                    || block.clone(),
+                )?;
+                let blocksize = match cut {
+                    Cut::Size(blocksize) => blocksize,
+                    Cut::Grow(next) => {
+                        length = next;
+                        continue;
+                    }
+                };
+
+                self.set_offset(self.offset() + blocksize);
+                block.truncate(blocksize as usize);
+                return Ok(Some(self.state_mut().emit(block)));
+            }
+        }
+    }
+
+    /// Get file size
+    fn filesize<P>(path: &P) -> error::Result<u64>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        filesize(path)
+    }
+
+    /// Fix blocksize to file size in file size is lower than blocksize
+    fn fix_blocksize<P>(path: &P, blocksize: u64) -> error::Result<u64>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        Ok(filesize(path)?.min(blocksize))
+    }
+
+    /// Get current value of offset
+    fn offset(&self) -> u64 {
+        self.state().offset
+    }
+
+    /// Get file length
+    fn file_length(&self) -> u64 {
+        self.state().file_length
+    }
+
+    /// Get bytes consumed against file length
+    fn progress(&self) -> Progress {
+        self.state().progress()
+    }
+
+    /// Get file
+    fn file(&self) -> &std::fs::File {
+        &self.state().file
+    }
+
+    /// Get offset where producer stop
+    fn end(&self) -> u64 {
+        self.state().end
+    }
+
+    /// Get path of file
+    fn path(&self) -> &std::path::Path {
+        &self.state().path
+    }
+
+    /// Get index of file, set in block produce
+    fn source_index(&self) -> usize {
+        self.state().source_index
+    }
+
+    /// Get blocksize
+    fn blocksize(&self) -> u64 {
+        self.state().blocksize
+    }
+
+    /// Get maximal size a block can grow to contain a complete record
+    fn max_blocksize(&self) -> u64 {
+        self.state().max_blocksize
+    }
+
+    /// Set value of offset
+    fn set_offset(&mut self, value: u64) {
+        self.state_mut().offset = value;
+    }
+
+    /// Set maximal size a block can grow, a value lower than blocksize disable growth
+    fn set_max_blocksize(&mut self, value: u64) {
+        self.state_mut().max_blocksize = value;
+    }
+
+    /// Set index of file, set in block produce
+    fn set_source_index(&mut self, value: usize) {
+        self.state_mut().source_index = value;
+    }
+
+    /// Get map options
+    fn map_options(&self) -> &MapOptions {
+        &self.state().map_options
+    }
+
+    /// Set map options, used by next mapping
+    fn set_map_options(&mut self, options: MapOptions) {
+        self.state_mut().map_options = options;
+    }
+
+    /// Get how malformed records are handled
+    fn parse_mode(&self) -> ParseMode {
+        self.state().parse_mode
+    }
+
+    /// Set how malformed records are handled, in [ParseMode::Lenient] a block without complete record is cut after its last line
+    fn set_parse_mode(&mut self, mode: ParseMode) {
+        self.state_mut().parse_mode = mode;
+    }
+
+    /// Allow data append to file while it's read, only a truncation of file is an [Error::FileChanged](error::Error::FileChanged), appended data is ignored
+    fn set_allow_append(&mut self, value: bool) {
+        self.state_mut().allow_append = value;
+    }
+
+    /// Map whole file once, next blocks are cheap to clone range of this mapping
+    fn set_map_once(&mut self) -> error::Result<()> {
+        self.state_mut().map_whole_file()
+    }
+
+    /// Take a shared advisory lock on file, writers that respect advisory lock wait until producer is drop
+    fn lock_file(&self) -> error::Result<()> {
+        self.state().lock_file()
+    }
+}
+
+/// Producer that chain blocks of many files
+///
+/// Files are opened only when previous file is completely read.
+pub struct MultiProducer<P> {
+    paths: Vec<std::path::PathBuf>,
+    lengths: Vec<u64>,
+    blocksize: u64,
+    index: usize,
+    current: Option<P>,
+    build: fn(u64, std::path::PathBuf) -> error::Result<P>,
+    parse_mode: ParseMode,
+}
+
+impl<P> MultiProducer<P>
+where
+    P: BlockProducer,
+{
+    /// Create a new multi file producer
+    pub fn new<I, T>(paths: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<std::path::Path>,
+    {
+        Self::with_blocksize(crate::DEFAULT_BLOCKSIZE, paths)
+    }
+
+    /// Create a new multi file producer with a blocksize choose by user
+    pub fn with_blocksize<I, T>(blocksize: u64, paths: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<std::path::Path>,
+    {
+        Self::with_constructor(blocksize, paths, P::with_blocksize)
+    }
+
+    /// Create a new multi file producer, each file producer is build by build function
+    pub fn with_constructor<I, T>(
+        blocksize: u64,
+        paths: I,
+        build: fn(u64, std::path::PathBuf) -> error::Result<P>,
+    ) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<std::path::Path>,
+    {
+        let paths: Vec<std::path::PathBuf> = paths
+            .into_iter()
+            .map(|path| path.as_ref().to_path_buf())
+            .collect();
+
+        Self {
+            lengths: paths
+                .iter()
+                .map(|path| filesize(path).unwrap_or(0))
+                .collect(),
+            paths,
+            blocksize,
+            index: 0,
+            current: None,
+            build,
+            parse_mode: ParseMode::default(),
+        }
+    }
+
+    /// Get next block
+    pub fn next_block(&mut self) -> error::Result<Option<Block>> {
+        loop {
+            let producer = match self.current.as_mut() {
+                Some(producer) => producer,
+                None => match self.paths.get(self.index) {
+                    Some(path) => {
+                        let mut producer = (self.build)(self.blocksize, path.clone())?;
+                        producer.set_source_index(self.index);
+                        producer.set_parse_mode(self.parse_mode);
+                        self.current.insert(producer)
+                    }
+                    None => return Ok(None),
+                },
+            };
+
+            match producer.next_block()? {
+                Some(block) => return Ok(Some(block)),
+                None => {
+                    self.current = None;
+                    self.index += 1;
+                }
+            }
+        }
+    }
+
+    /// Get paths of files
+    pub fn paths(&self) -> &[std::path::PathBuf] {
+        &self.paths
+    }
+
+    /// Set how malformed records are handled by producers of next files
+    pub fn set_parse_mode(&mut self, mode: ParseMode) {
+        self.parse_mode = mode;
+    }
+
+    /// Get bytes consumed against sum of files length
+    pub fn progress(&self) -> Progress {
+        let done = self.index.min(self.lengths.len());
+        let current = self
+            .current
+            .as_ref()
+            .map(|producer| producer.progress().consumed)
+            .unwrap_or(0);
+
+        Progress {
+            consumed: self.lengths[..done].iter().sum::<u64>() + current,
+            total: self.lengths.iter().sum(),
+        }
+    }
+}
+
+impl<P> Iterator for MultiProducer<P>
+where
+    P: BlockProducer,
+{
+    type Item = error::Result<Block>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_block().transpose()
+    }
+}
+
+/// Iterate over position of line begin at or after from, position 0 is considered as a line begin
+pub fn line_starts(data: &[u8], from: usize) -> impl Iterator<Item = usize> + '_ {
+    let next_line = move |pos: usize| {
+        data.get(pos..)
+            .and_then(|slice| slice.iter().position(|c| *c == b'\n'))
+            .map(|newline| pos + newline + 1)
+            .filter(|begin| *begin < data.len())
+    };
+
+    let first = if from == 0 {
+        Some(0).filter(|_| !data.is_empty())
+    } else {
+        next_line(from - 1)
+    };
+
+    std::iter::successors(first, move |pos| next_line(*pos))
 }
 
-impl Block {
-    /// Create a new Block
-    pub fn new(end: usize, mem: memmap2::Mmap) -> Self {
-        Self { mem, end }
+/// Split data in mutable slices, ranges must be sorted and not overlap
+pub fn split_ranges_mut<const N: usize>(
+    mut data: &mut [u8],
+    ranges: [std::ops::Range<usize>; N],
+) -> [&mut [u8]; N] {
+    let mut slices = Vec::with_capacity(N);
+    let mut consumed = 0;
+
+    for range in ranges {
+        let (_, rest) = std::mem::take(&mut data).split_at_mut(range.start - consumed);
+        let (slice, rest) = rest.split_at_mut(range.len());
+
+        slices.push(slice);
+        data = rest;
+        consumed = range.end;
     }
 
-    /// Acces to data owned by block
-    pub fn data(&self) -> &[u8] {
-        &self.mem[..self.end]
+    match slices.try_into() {
+        Ok(slices) => slices,
+        Err(_) => unreachable!(),
     }
+}
 
-    /// Get length of block
-    pub fn len(&self) -> usize {
-        self.mem[..self.end].len()
+/// Write data of blocks in a file, blocks are written in iteration order
+pub fn write_blocks<I, P>(blocks: I, path: P) -> error::Result<()>
+where
+    I: IntoIterator<Item = Block>,
+    P: AsRef<std::path::Path>,
+{
+    use std::io::Write as _;
+
+    let mut output = std::io::BufWriter::new(
+        std::fs::File::create(path).map_err(|source| error::Error::OpenFile { source })?,
+    );
+
+    for block in blocks {
+        output.write_all(block.data())?;
     }
 
-    /// Return true if the block is empty
-    pub fn is_empty(&self) -> bool {
-        self.len() == 0
+    output.flush()?;
+
+    Ok(())
+}
+
+/// Add location of position in block to error: path, absolute offset, block index and offset, line number in block and an excerpt of data
+pub fn locate(error: error::Error, block: &Block, position: usize) -> error::Error {
+    if let error::Error::Located { .. } = error {
+        return error;
+    }
+
+    let position = position.min(block.len());
+
+    error::Error::Located {
+        source: Box::new(error),
+        path: block.path().map(std::path::Path::to_path_buf),
+        offset: block.offset() + position as u64,
+        block: block.index(),
+        block_offset: block.offset(),
+        line: block.line_at(position),
+        excerpt: block.data()[position..]
+            .iter()
+            .take(EXCERPT_LENGTH)
+            .flat_map(|c| std::ascii::escape_default(*c))
+            .map(char::from)
+            .collect(),
     }
 }
 
-/// Macro that generate a Block Producer struct.
+/// Result of [cut_block]
+pub(crate) enum Cut {
+    /// Block end after this number of bytes
+    Size(u64),
+
+    /// No complete record in window, retry with a window of this length
+    Grow(u64),
+}
+
+/// Choose where a block end in a window of input that begin at a record
 ///
-/// Each block are shrink to remove last partial record
-#[macro_export(local_inner_macros)]
-macro_rules! impl_producer {
-    ($name:ident, $correct_block_size:expr) => {
-        /// $name to produce block
-        pub struct $name {
-            offset: u64,
-            blocksize: u64,
-            file: std::fs::File,
-            file_length: u64,
+/// If no complete record fit in window, window length is doubled until max_blocksize is reach. Other errors of correct_block_size are format errors: in [ParseMode::Lenient] block is cut after its last line terminator and readers skip partial records at its ends, in [ParseMode::Strict] error is returned with location of window build by `block`.
+pub(crate) fn cut_block<F>(
+    window: &[u8],
+    max_blocksize: u64,
+    parse_mode: ParseMode,
+    correct_block_size: fn(&[u8]) -> error::Result<u64>,
+    block: F,
+) -> error::Result<Cut>
+where
+    F: FnOnce() -> Block,
+{
+    let length = window.len() as u64;
+
+    match correct_block_size(window) {
+        Ok(blocksize) => Ok(Cut::Size(blocksize)),
+        Err(error::Error::NoNewLineInBlock) if length < max_blocksize => {
+            let length = (length * 2).max(1).min(max_blocksize);
+            log::debug!("No complete record in window grow it to {}", length);
+
+            Ok(Cut::Grow(length))
         }
+        Err(e) => {
+            let block = block();
 
-        impl $name {
-            /// Create a new Block producer
-            #[inline(always)]
-            pub fn new<P>(path: P) -> error::Result<Self>
-            where
-                P: AsRef<std::path::Path>,
-                Self: Sized,
-            {
-                Self::with_blocksize($crate::DEFAULT_BLOCKSIZE, path)
-            }
+            match window.iter().rposition(|c| *c == b'\n') {
+                Some(pos) if parse_mode == ParseMode::Lenient => {
+                    log::warn!(
+                        "No complete record at offset {} in {} bytes, cut block at last line",
+                        block.offset(),
+                        block.len()
+                    );
 
-            /// Create a new Block producer with a blocksize choose by user
-            pub fn with_blocksize<P>(blocksize: u64, path: P) -> error::Result<Self>
-            where
-                P: AsRef<std::path::Path>,
-            {
-                Ok(Self {
-                    offset: 0,
-                    blocksize: Self::fix_blocksize::<P>(&path, blocksize)?,
-                    file_length: Self::filesize::<P>(&path)?,
-                    file: std::fs::File::open(path)
-                        .map_err(|source| error::Error::OpenFile { source })?,
-                })
+                    Ok(Cut::Size((pos + 1) as u64))
+                }
+                _ => Err(locate(e, &block, 0)),
             }
+        }
+    }
+}
 
-            /// Create a new Block producer with offset choose by user
-            pub fn with_offset<P>(offset: u64, path: P) -> error::Result<Self>
-            where
-                P: AsRef<std::path::Path>,
-            {
-                Ok(Self {
-                    offset: offset,
-                    blocksize: Self::fix_blocksize::<P>(&path, $crate::DEFAULT_BLOCKSIZE)?,
-                    file_length: Self::filesize::<P>(&path)?,
-                    file: std::fs::File::open(path)
-                        .map_err(|source| error::Error::OpenFile { source })?,
-                })
-            }
+/// Number of bytes of input show in error
+const EXCERPT_LENGTH: usize = 40;
 
-            /// Create a new Block producer with offset choose by user
-            pub fn with_blocksize_offset<P>(
-                blocksize: u64,
-                offset: u64,
-                path: P,
-            ) -> error::Result<Self>
-            where
-                P: AsRef<std::path::Path>,
-            {
-                Ok(Self {
-                    offset: offset,
-                    blocksize: Self::fix_blocksize::<P>(&path, blocksize)?,
-                    file_length: Self::filesize::<P>(&path)?,
-                    file: std::fs::File::open(path)
-                        .map_err(|source| error::Error::OpenFile { source })?,
-                })
-            }
+/// Count number of line terminator in data
+fn count_lines(data: &[u8]) -> u64 {
+    data.iter().filter(|c| **c == b'\n').count() as u64
+}
 
-            /// Get next block
-            pub fn next_block(&mut self) -> error::Result<Option<block::Block>> {
-                if self.offset() == self.file_length() {
-                    Ok(None)
-                } else if self.offset() + self.blocksize() >= self.file_length() {
-                    let block = unsafe {
-                        memmap2::MmapOptions::new()
-                            .offset(self.offset())
-                            .len((self.file_length() - self.offset()) as usize)
-                            .map(self.file())
-                            .map_err(|source| error::Error::MapFile { source })?
-                    };
-
-                    self.set_offset(self.file_length());
-
-                    Ok(Some(block::Block::new(block.len(), block)))
-                } else {
-                    let block = unsafe {
-                        memmap2::MmapOptions::new()
-                            .offset(self.offset())
-                            .len(self.blocksize() as usize)
-                            .map(self.file())
-                            .map_err(|source| error::Error::MapFile { source })?
-                    };
-
-                    let blocksize = Self::correct_block_size(&block)?;
-                    self.set_offset(self.offset() + blocksize);
-                    Ok(Some(block::Block::new(blocksize as usize, block)))
-                }
-            }
+/// Length of begin of file sampled to estimate blocksize
+const SAMPLE_LENGTH: u64 = 1 << 20;
 
-            /// Get file size
-            pub fn filesize<P>(path: &P) -> error::Result<u64>
-            where
-                P: AsRef<std::path::Path>,
-            {
-                Ok(path
-                    .as_ref()
-                    .metadata()
-                    .map_err(|source| error::Error::MetaDataFile { source })?
-                    .len())
-            }
+/// Get size of memory page
+fn page_size() -> u64 {
+    #[cfg(unix)]
+    {
+        let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        if size > 0 {
+            return size as u64;
+        }
+    }
 
-            /// Fix blocksize to file size in file size is lower than blocksize
-            pub fn fix_blocksize<P>(path: &P, blocksize: u64) -> error::Result<u64>
-            where
-                P: AsRef<std::path::Path>,
-                Self: Sized,
-            {
-                Ok(Self::filesize::<P>(path)?.min(blocksize))
-            }
+    4096
+}
 
-            /// Search the begin of the partial record at the end of [Block](Block)
-            #[inline(always)]
-            pub fn correct_block_size(block: &[u8]) -> error::Result<u64> {
-                $correct_block_size(block)
-            }
+/// Check if file begin by gzip magic number, file cursor is rewind
+#[cfg(feature = "gzip")]
+fn is_gzip(mut file: &std::fs::File) -> error::Result<bool> {
+    use std::io::Read as _;
+    use std::io::Seek as _;
 
-            /// Get current value of offset
-            pub fn offset(&self) -> u64 {
-                self.offset
-            }
+    let mut magic = [0; 2];
+    let gzip = match file.read_exact(&mut magic) {
+        Ok(()) => magic == [0x1f, 0x8b],
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => false,
+        Err(e) => return Err(e.into()),
+    };
 
-            /// Get file length
-            pub fn file_length(&self) -> u64 {
-                self.file_length
-            }
+    file.rewind()?;
 
-            /// Get file
-            pub fn file(&self) -> &std::fs::File {
-                &self.file
-            }
+    Ok(gzip)
+}
 
-            /// Get blocksize
-            pub fn blocksize(&self) -> u64 {
-                self.blocksize
-            }
+/// Compressed reader and a counter of compressed bytes already read
+#[cfg(feature = "gzip")]
+type GzipReader = (
+    Box<dyn std::io::Read + Send>,
+    std::sync::Arc<std::sync::atomic::AtomicU64>,
+);
 
-            /// Set value of offset
-            pub fn set_offset(&mut self, value: u64) {
-                self.offset = value;
-            }
-        }
+/// Build a reader that decompress file, BGZF file are decompressed in parallel
+#[cfg(feature = "gzip")]
+fn gzip_reader(file: &std::fs::File) -> error::Result<GzipReader> {
+    #[cfg(feature = "bgzf")]
+    {
+        let mapping = unsafe { memmap2::Mmap::map(file) }
+            .map_err(|source| error::Error::MapFile { source })?;
 
-        impl Iterator for $name {
-            type Item = error::Result<block::Block>;
+        if bgzf::is_bgzf(&mapping) {
+            let reader = bgzf::Reader::new(mapping);
+            let counter = reader.counter();
 
-            fn next(&mut self) -> Option<Self::Item> {
-                match self.next_block() {
-                    Ok(Some(block)) => Some(Ok(block)),
-                    Ok(None) => None,
-                    Err(e) => Some(Err(e)),
-                }
-            }
+            return Ok((Box::new(reader), counter));
         }
+    }
+
+    let counter = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
+    let reader = Counter {
+        inner: file.try_clone()?,
+        count: counter.clone(),
     };
+
+    Ok((
+        Box::new(flate2::read::MultiGzDecoder::new(std::io::BufReader::new(
+            reader,
+        ))),
+        counter,
+    ))
+}
+
+/// Reader that count bytes read in a shared counter
+#[cfg(feature = "gzip")]
+struct Counter<R> {
+    inner: R,
+    count: std::sync::Arc<std::sync::atomic::AtomicU64>,
+}
+
+#[cfg(feature = "gzip")]
+impl<R> std::io::Read for Counter<R>
+where
+    R: std::io::Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let length = self.inner.read(buf)?;
+
+        self.count
+            .fetch_add(length as u64, std::sync::atomic::Ordering::Relaxed);
+
+        Ok(length)
+    }
+}
+
+/// Get size of file
+fn filesize<P>(path: &P) -> error::Result<u64>
+where
+    P: AsRef<std::path::Path>,
+{
+    Ok(path
+        .as_ref()
+        .metadata()
+        .map_err(|source| error::Error::MetaDataFile { source })?
+        .len())
+}
+
+/// Trait of readers that split a [Block] in records
+///
+/// It's a lending iterator, a record borrow reader until it's drop, so code generic over any format could be write with a `while let` loop.
+pub trait RecordReader: Sized {
+    /// Type of record produce by reader
+    type Record<'a>
+    where
+        Self: 'a;
+
+    /// Build a reader on a block
+    fn from_block(block: Block) -> Self;
+
+    /// Get the next available record
+    fn next_record(&mut self) -> error::Result<Option<Self::Record<'_>>>;
+
+    /// Set how malformed records are handled
+    fn set_parse_mode(&mut self, mode: ParseMode);
+
+    /// Get data skipped in [ParseMode::Lenient]
+    fn skipped(&self) -> Skipped;
 }
 
 /// Macro that generate a Reader struct.
@@ -209,23 +1461,68 @@ macro_rules! impl_producer {
 /// Split of block in record is perform by a user define closure.
 #[macro_export(local_inner_macros)]
 macro_rules! impl_reader {
-    ($name:ident, $lt:lifetime, $record:ty, $next_record:expr) => {
+    ($name:ident, $lt:lifetime, $record:ty, $options:ty, $next_record:expr, $skip:expr, $resync:expr) => {
 	/// $name is a reader struct
         pub struct $name {
             offset: usize,
             block: block::Block,
+            parse_mode: block::ParseMode,
+            skipped: block::Skipped,
+            options: $options,
         }
 
-        impl<$lt> $name {
+        impl $name {
 	    /// Create a new $name
             pub fn new(block: block::Block) -> Self {
-                Self { offset: 0, block }
+                Self {
+                    offset: 0,
+                    block,
+                    parse_mode: block::ParseMode::default(),
+                    skipped: block::Skipped::default(),
+                    options: <$options>::default(),
+                }
             }
 
-	    /// Get the next available record
+	    /// Get the next available record, record borrow reader until it's drop
+	    ///
+	    /// In [ParseMode::Lenient](block::ParseMode::Lenient) a malformed record is logged with its byte offset and skipped, reader resync on next valid record. In [ParseMode::Strict](block::ParseMode::Strict) returned error is located with its line number.
             #[inline(always)]
-            pub fn next_record(&$lt mut self) -> error::Result<Option<$record>> {
-                $next_record(&mut self.block, &mut self.offset)
+            pub fn next_record<$lt>(&$lt mut self) -> error::Result<Option<$record>> {
+                loop {
+                    let mut start = self.offset;
+                    $skip(&self.block, &mut start);
+
+                    match $next_record(&self.block, &mut self.offset, self.parse_mode, &self.options) {
+                        Ok(record) => return Ok(record),
+                        Err(e) if self.parse_mode == block::ParseMode::Lenient => {
+                            let next = $resync(self.block.data(), start + 1)
+                                .max(start + 1)
+                                .min(self.block.len());
+
+                            log::warn!(
+                                "Skip {} bytes at offset {}: {}",
+                                next - start,
+                                self.block.offset() + start as u64,
+                                e
+                            );
+
+                            self.skipped.records += 1;
+                            self.skipped.bytes += (next - start) as u64;
+                            self.offset = next;
+                        }
+                        Err(e) => return Err(block::locate(e, &self.block, start)),
+                    }
+                }
+            }
+
+	    /// Set how malformed records are handled
+            pub fn set_parse_mode(&mut self, mode: block::ParseMode) {
+                self.parse_mode = mode;
+            }
+
+	    /// Get data skipped in [ParseMode::Lenient](block::ParseMode::Lenient)
+            pub fn skipped(&self) -> block::Skipped {
+                self.skipped
             }
 
 	    /// A utils function to get range of the next line
@@ -239,6 +1536,81 @@ macro_rules! impl_reader {
 
                 Ok(range)
             }
+
+	    /// Move offset after empty lines (`\n` or `\r\n`)
+            pub fn skip_empty_lines(block: &block::Block, offset: &mut usize) {
+                let data = block.data();
+
+                loop {
+                    match &data[*offset..] {
+                        [b'\n', ..] => *offset += 1,
+                        [b'\r', b'\n', ..] => *offset += 2,
+                        [b'\r'] => *offset += 1,
+                        _ => return,
+                    }
+                }
+            }
+
+	    /// Get next line without line terminator (`\n` or `\r\n`) and move offset after it
+	    ///
+	    /// Last line of block could not be terminated.
+            pub fn next_line<'b>(
+                block: &'b block::Block,
+                offset: &mut usize,
+            ) -> error::Result<&'b [u8]> {
+                Ok(&block.data()[Self::next_line_range(block, offset)?])
+            }
+
+	    /// Get range of next line without line terminator (`\n` or `\r\n`) and move offset after it
+            pub fn next_line_range(
+                block: &block::Block,
+                offset: &mut usize,
+            ) -> error::Result<std::ops::Range<usize>> {
+                let data = block.data();
+
+                if *offset >= data.len() {
+                    return Err(error::Error::PartialRecord);
+                }
+
+                let begin = *offset;
+                let mut end = match memchr::memchr(b'\n', &data[begin..]) {
+                    Some(next) => {
+                        *offset += next + 1;
+                        begin + next
+                    }
+                    None => {
+                        *offset = data.len();
+                        data.len()
+                    }
+                };
+
+                if end > begin && data[end - 1] == b'\r' {
+                    end -= 1;
+                }
+
+                Ok(begin..end)
+            }
+        }
+
+        impl block::RecordReader for $name {
+            type Record<$lt> = $record;
+
+            fn from_block(block: block::Block) -> Self {
+                Self::new(block)
+            }
+
+            #[inline(always)]
+            fn next_record(&mut self) -> error::Result<Option<Self::Record<'_>>> {
+                $name::next_record(self)
+            }
+
+            fn set_parse_mode(&mut self, mode: block::ParseMode) {
+                $name::set_parse_mode(self, mode)
+            }
+
+            fn skipped(&self) -> block::Skipped {
+                $name::skipped(self)
+            }
         }
     };
 }
@@ -284,4 +1656,393 @@ mod tests {
 
         Ok(())
     }
+
+    /// A producer where each line is a record
+    pub(crate) struct LineProducer {
+        state: ProducerState,
+    }
+
+    impl BlockProducer for LineProducer {
+        fn from_state(state: ProducerState) -> Self {
+            Self { state }
+        }
+
+        fn state(&self) -> &ProducerState {
+            &self.state
+        }
+
+        fn state_mut(&mut self) -> &mut ProducerState {
+            &mut self.state
+        }
+
+        fn correct_block_size(block: &[u8]) -> error::Result<u64> {
+            block
+                .iter()
+                .rposition(|c| *c == b'\n')
+                .map(|pos| (pos + 1) as u64)
+                .ok_or(error::Error::NoNewLineInBlock)
+        }
+    }
+
+    #[test]
+    fn custom_producer() -> error::Result<()> {
+        let file = crate::tests::generate_fastq(42, 10, 50)?;
+
+        let mut producer = LineProducer::with_blocksize(100, file.path())?;
+
+        let mut block_length = Vec::new();
+        while let Some(block) = producer.next_block()? {
+            assert_eq!(block.data().last(), Some(&b'\n'));
+            assert_eq!(block.index(), block_length.len() as u64);
+            assert_eq!(block.offset(), block_length.iter().sum::<usize>() as u64);
+            assert_eq!(block.origin_at(10).file_offset, block.offset() + 10);
+            block_length.push(block.len());
+        }
+
+        assert_eq!(
+            block_length.iter().sum::<usize>() as u64,
+            producer.file_length()
+        );
+        assert_eq!(producer.offset(), producer.file_length());
+
+        Ok(())
+    }
+
+    #[test]
+    fn map_once() -> error::Result<()> {
+        let file = crate::tests::generate_fastq(42, 100, 50)?;
+
+        let mut producer = LineProducer::with_blocksize(1000, file.path())?;
+        let mut shared = LineProducer::with_blocksize(1000, file.path())?;
+        shared.set_map_once()?;
+
+        let mut blocks = Vec::new();
+        while let Some(block) = shared.next_block()? {
+            assert_eq!(
+                Some(block.data()),
+                producer.next_block()?.as_ref().map(Block::data)
+            );
+            blocks.push(block);
+        }
+        assert!(producer.next_block()?.is_none());
+
+        let lengths = std::thread::spawn({
+            let blocks = blocks.clone();
+            move || blocks.iter().map(Block::len).collect::<Vec<usize>>()
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(
+            lengths,
+            blocks.iter().map(Block::len).collect::<Vec<usize>>()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn multi_producer() -> error::Result<()> {
+        let files = [
+            crate::tests::generate_fastq(42, 10, 50)?,
+            crate::tests::generate_fastq(43, 20, 50)?,
+            crate::tests::generate_fastq(44, 30, 50)?,
+        ];
+
+        let mut producer = MultiProducer::<LineProducer>::with_blocksize(
+            1000,
+            files.iter().map(|file| file.path()),
+        );
+
+        let mut lengths = vec![0; 3];
+        while let Some(block) = producer.next_block()? {
+            assert_eq!(block.path(), Some(files[block.source_index()].path()));
+            assert_eq!(block.origin().source_index, block.source_index());
+
+            lengths[block.source_index()] += block.len();
+        }
+
+        assert_eq!(lengths, vec![1080, 2180, 3280]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn progress() -> error::Result<()> {
+        let files = [
+            crate::tests::generate_fastq(42, 10, 50)?,
+            crate::tests::generate_fastq(43, 20, 50)?,
+        ];
+
+        let mut producer = LineProducer::with_blocksize(1000, files[0].path())?;
+        assert_eq!(
+            producer.progress(),
+            Progress {
+                consumed: 0,
+                total: 1080
+            }
+        );
+
+        let mut previous = 0;
+        while producer.next_block()?.is_some() {
+            assert!(producer.progress().consumed > previous);
+            previous = producer.progress().consumed;
+        }
+        assert_eq!(producer.progress().fraction(), 1.0);
+
+        let mut producer = MultiProducer::<LineProducer>::with_blocksize(
+            1000,
+            files.iter().map(|file| file.path()),
+        );
+
+        let mut consumed = Vec::new();
+        while producer.next_block()?.is_some() {
+            consumed.push(producer.progress().consumed);
+        }
+
+        assert_eq!(
+            producer.progress(),
+            Progress {
+                consumed: 3260,
+                total: 3260
+            }
+        );
+        assert_eq!(consumed, vec![975, 1080, 2055, 3044, 3260]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn file_changed() -> error::Result<()> {
+        let file = crate::tests::generate_fastq(42, 100, 50)?;
+
+        let mut producer = LineProducer::with_blocksize(1000, file.path())?;
+        producer.lock_file()?;
+        assert!(producer.next_block()?.is_some());
+
+        file.as_file().set_len(2000)?;
+        assert!(matches!(
+            producer.next_block(),
+            Err(error::Error::FileChanged { .. })
+        ));
+
+        let mut producer = LineProducer::with_blocksize(1000, file.path())?;
+        producer.set_map_once()?;
+        assert!(producer.next_block()?.is_some());
+
+        file.as_file().set_len(1000)?;
+        assert!(matches!(
+            producer.next_block(),
+            Err(error::Error::FileChanged { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn file_rewrite() -> error::Result<()> {
+        use std::io::Write as _;
+
+        let file = crate::tests::generate_fastq(42, 100, 50)?;
+        let mut data = std::fs::read(file.path())?;
+
+        let mut producer = LineProducer::with_blocksize(1000, file.path())?;
+        assert!(producer.next_block()?.is_some());
+
+        // let modification time move forward on file system with a coarse clock
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        data.reverse();
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(file.path())?
+            .write_all(&data)?;
+
+        assert!(matches!(
+            producer.next_block(),
+            Err(error::Error::FileChanged { .. })
+        ));
+
+        let mut producer = LineProducer::with_blocksize(1000, file.path())?;
+        assert!(producer.next_block()?.is_some());
+
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(file.path())?
+            .write_all(b"@appended\nACGT\n+\n!!!!\n")?;
+
+        assert!(matches!(
+            producer.next_block(),
+            Err(error::Error::FileChanged { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn file_append() -> error::Result<()> {
+        use std::io::Write as _;
+
+        let file = crate::tests::generate_fastq(42, 100, 50)?;
+        let data = std::fs::read(file.path())?;
+        let mut append = std::fs::OpenOptions::new().append(true).open(file.path())?;
+
+        let mut producer = LineProducer::with_blocksize(1000, file.path())?;
+        producer.set_allow_append(true);
+        let mut reverse = reverse::Producer::<LineProducer>::with_blocksize(1000, file.path())?;
+        reverse.inner_mut().set_allow_append(true);
+
+        let mut forward = Vec::new();
+        let mut backward = Vec::new();
+        loop {
+            append.write_all(b"@appended\nACGT\n+\n!!!!\n")?;
+
+            let block = producer.next_block()?;
+            if let Some(block) = reverse.next_block()? {
+                backward.push(block);
+            }
+
+            match block {
+                Some(block) => forward.extend_from_slice(block.data()),
+                None => break,
+            }
+        }
+        while let Some(block) = reverse.next_block()? {
+            backward.push(block);
+        }
+
+        assert_eq!(forward, data);
+        assert_eq!(
+            backward
+                .iter()
+                .rev()
+                .flat_map(|block| block.data().to_vec())
+                .collect::<Vec<u8>>(),
+            data
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn copy_on_write() -> error::Result<()> {
+        let file = crate::tests::generate_fastq(42, 10, 50)?;
+        let data = std::fs::read(file.path())?;
+
+        let mut producer = LineProducer::with_blocksize(500, file.path())?;
+        producer.set_map_options(MapOptions {
+            copy_on_write: true,
+            ..MapOptions::default()
+        });
+
+        let mut blocks = Vec::new();
+        while let Some(mut block) = producer.next_block()? {
+            let shared = block.clone();
+            block.data_mut().make_ascii_lowercase();
+
+            assert_eq!(
+                shared.data(),
+                &data[shared.offset() as usize..][..shared.len()]
+            );
+            blocks.push(block);
+        }
+
+        let output = tempfile::NamedTempFile::new()?;
+        write_blocks(blocks, output.path())?;
+
+        assert_eq!(std::fs::read(file.path())?, data);
+        assert_eq!(std::fs::read(output.path())?, data.to_ascii_lowercase());
+
+        Ok(())
+    }
+
+    #[test]
+    fn split_ranges() {
+        let mut data = *b"0123456789";
+
+        let [first, second] = split_ranges_mut(&mut data, [1..3, 5..9]);
+        first.copy_from_slice(b"ab");
+        second.copy_from_slice(b"cdef");
+
+        assert_eq!(&data, b"0ab34cdef9");
+    }
+
+    #[test]
+    fn map_options() -> error::Result<()> {
+        let file = crate::tests::generate_fastq(42, 100, 50)?;
+        let options = MapOptions {
+            sequential: true,
+            prefetch: true,
+            drop_consumed: true,
+            populate: true,
+            lock: false,
+            copy_on_write: false,
+        };
+
+        let mut producer = LineProducer::with_blocksize(1000, file.path())?;
+        let mut advised = LineProducer::with_blocksize(1000, file.path())?;
+        advised.set_map_options(options.clone());
+        let mut shared = LineProducer::with_blocksize(1000, file.path())?;
+        shared.set_map_options(options);
+        shared.set_map_once()?;
+
+        while let Some(block) = producer.next_block()? {
+            assert_eq!(
+                Some(block.data()),
+                advised.next_block()?.as_ref().map(Block::data)
+            );
+            assert_eq!(
+                Some(block.data()),
+                shared.next_block()?.as_ref().map(Block::data)
+            );
+        }
+        assert!(advised.next_block()?.is_none());
+        assert!(shared.next_block()?.is_none());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn gzip() -> error::Result<()> {
+        use std::io::Write as _;
+
+        let file = crate::tests::generate_fastq(42, 100, 50)?;
+        let data = std::fs::read(file.path())?;
+
+        // two gzip members, like a concatenation of compressed files
+        let mut compressed = tempfile::NamedTempFile::new()?;
+        let (first, second) = data.split_at(data.len() / 2);
+        for part in [first, second] {
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(part)?;
+            compressed.write_all(&encoder.finish()?)?;
+        }
+
+        let mut plain = LineProducer::with_blocksize(1000, file.path())?;
+        let mut gzip = LineProducer::with_blocksize(1000, compressed.path())?;
+
+        while let Some(block) = plain.next_block()? {
+            let other = gzip.next_block()?.unwrap();
+
+            assert_eq!(block.data(), other.data());
+            assert_eq!(block.offset(), other.offset());
+            assert_eq!(block.index(), other.index());
+            assert_eq!(other.path(), Some(compressed.path()));
+        }
+        assert!(gzip.next_block()?.is_none());
+        assert_eq!(gzip.offset(), data.len() as u64);
+        assert_eq!(gzip.progress().fraction(), 1.0);
+
+        let mut skip = LineProducer::with_blocksize_offset(1000, 1080, compressed.path())?;
+        assert_eq!(skip.next_block()?.unwrap().offset(), 1080);
+
+        let mut gzip = LineProducer::new(compressed.path())?;
+        assert!(matches!(
+            gzip.set_map_once(),
+            Err(error::Error::NotMappable)
+        ));
+
+        Ok(())
+    }
 }
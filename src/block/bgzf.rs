@@ -0,0 +1,282 @@
+//! Struct that decompress a BGZF file in parallel, each BGZF block is an independent deflate stream.
+
+/* std use */
+use std::io::Read as _;
+
+/* crate use */
+use rayon::prelude::*;
+
+/* project use */
+use crate::error;
+
+/// Size of BGZF block footer, crc32 and uncompressed size
+const FOOTER_LENGTH: usize = 8;
+
+/// Maximal size of an inflated BGZF block
+const MAX_INFLATED_LENGTH: usize = 1 << 16;
+
+/// Check if data begin by a BGZF block header
+pub fn is_bgzf(data: &[u8]) -> bool {
+    block_length(data).is_some()
+}
+
+/// Get length of BGZF block at begin of data, None if data didn't begin by a BGZF header
+fn block_length(data: &[u8]) -> Option<usize> {
+    if data.len() < 12 || data[..4] != [0x1f, 0x8b, 0x08, 0x04] {
+        return None;
+    }
+
+    let xlen = u16::from_le_bytes([data[10], data[11]]) as usize;
+    let mut extra = data.get(12..12 + xlen)?;
+
+    while extra.len() >= 4 {
+        let slen = u16::from_le_bytes([extra[2], extra[3]]) as usize;
+
+        if extra[..2] == *b"BC" && slen == 2 {
+            let bsize = u16::from_le_bytes([*extra.get(4)?, *extra.get(5)?]) as usize;
+
+            return Some(bsize + 1).filter(|length| *length >= 12 + xlen + FOOTER_LENGTH);
+        }
+
+        extra = extra.get(4 + slen..)?;
+    }
+
+    None
+}
+
+/// Decompress one BGZF block and check its crc32
+fn inflate(data: &[u8], xlen: usize) -> Option<Vec<u8>> {
+    let footer = &data[data.len() - FOOTER_LENGTH..];
+    let crc = u32::from_le_bytes([footer[0], footer[1], footer[2], footer[3]]);
+    let length = u32::from_le_bytes([footer[4], footer[5], footer[6], footer[7]]) as usize;
+    if length > MAX_INFLATED_LENGTH {
+        return None;
+    }
+
+    let mut buffer = Vec::with_capacity(length);
+    flate2::read::DeflateDecoder::new(&data[12 + xlen..data.len() - FOOTER_LENGTH])
+        .read_to_end(&mut buffer)
+        .ok()?;
+
+    let mut check = flate2::Crc::new();
+    check.update(&buffer);
+
+    Some(buffer).filter(|buffer| buffer.len() == length && check.sum() == crc)
+}
+
+/// Reader that decompress a mapped BGZF file
+///
+/// Block boundaries are found in mapping, a batch of blocks is inflate in parallel with rayon each time reader need data.
+pub struct Reader {
+    mapping: memmap2::Mmap,
+    position: usize,
+    batch: usize,
+    buffer: Vec<u8>,
+    consumed: usize,
+    counter: std::sync::Arc<std::sync::atomic::AtomicU64>,
+}
+
+impl Reader {
+    /// Create a new reader, mapping must begin by a BGZF block
+    pub fn new(mapping: memmap2::Mmap) -> Self {
+        Self::with_batch(rayon::current_num_threads() * 4, mapping)
+    }
+
+    /// Create a new reader that inflate batch BGZF blocks at once
+    pub fn with_batch(batch: usize, mapping: memmap2::Mmap) -> Self {
+        Self {
+            mapping,
+            position: 0,
+            batch: batch.max(1),
+            buffer: Vec::new(),
+            consumed: 0,
+            counter: std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0)),
+        }
+    }
+
+    /// Get offset, in compressed file, of next BGZF block to inflate
+    pub fn position(&self) -> u64 {
+        self.position as u64
+    }
+
+    /// Get a shared counter of compressed bytes already inflated
+    pub fn counter(&self) -> std::sync::Arc<std::sync::atomic::AtomicU64> {
+        self.counter.clone()
+    }
+
+    /// Inflate next batch of BGZF blocks in buffer
+    fn fill(&mut self) -> error::Result<()> {
+        let mut ranges = Vec::with_capacity(self.batch);
+
+        while ranges.len() < self.batch && self.position < self.mapping.len() {
+            let length = block_length(&self.mapping[self.position..])
+                .filter(|length| self.position + length <= self.mapping.len())
+                .ok_or(error::Error::InvalidBgzfBlock {
+                    offset: self.position as u64,
+                })?;
+
+            ranges.push(self.position..self.position + length);
+            self.position += length;
+        }
+
+        let blocks = ranges
+            .into_par_iter()
+            .map(|range| {
+                let data = &self.mapping[range.clone()];
+                let xlen = u16::from_le_bytes([data[10], data[11]]) as usize;
+
+                inflate(data, xlen).ok_or(error::Error::InvalidBgzfBlock {
+                    offset: range.start as u64,
+                })
+            })
+            .collect::<error::Result<Vec<Vec<u8>>>>()?;
+
+        self.counter
+            .store(self.position as u64, std::sync::atomic::Ordering::Relaxed);
+        self.buffer.clear();
+        self.consumed = 0;
+        for block in blocks {
+            self.buffer.extend_from_slice(&block);
+        }
+
+        Ok(())
+    }
+}
+
+impl std::io::Read for Reader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.consumed == self.buffer.len() {
+            if self.position >= self.mapping.len() {
+                return Ok(0);
+            }
+
+            self.fill()
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        }
+
+        let length = buf.len().min(self.buffer.len() - self.consumed);
+        buf[..length].copy_from_slice(&self.buffer[self.consumed..self.consumed + length]);
+        self.consumed += length;
+
+        Ok(length)
+    }
+}
+
+impl std::fmt::Debug for Reader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Reader")
+            .field("position", &self.position)
+            .field("batch", &self.batch)
+            .field("buffer_length", &self.buffer.len())
+            .field("consumed", &self.consumed)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    use crate::block::tests::LineProducer;
+    use crate::block::BlockProducer as _;
+
+    use std::io::Write as _;
+
+    /// Size of BGZF block header, extra subfield BC included
+    const HEADER_LENGTH: usize = 18;
+
+    /// Compress data in BGZF format, each BGZF block contains chunk bytes, an empty end of file block is added
+    pub(crate) fn compress(data: &[u8], chunk: usize) -> error::Result<Vec<u8>> {
+        let mut output = Vec::new();
+
+        for part in data.chunks(chunk).chain(std::iter::once(&[][..])) {
+            let mut encoder =
+                flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(part)?;
+            let deflate = encoder.finish()?;
+
+            let mut crc = flate2::Crc::new();
+            crc.update(part);
+
+            let bsize = (HEADER_LENGTH + deflate.len() + FOOTER_LENGTH - 1) as u16;
+
+            output.extend_from_slice(&[0x1f, 0x8b, 0x08, 0x04, 0, 0, 0, 0, 0, 0xff, 6, 0]);
+            output.extend_from_slice(b"BC");
+            output.extend_from_slice(&2u16.to_le_bytes());
+            output.extend_from_slice(&bsize.to_le_bytes());
+            output.extend_from_slice(&deflate);
+            output.extend_from_slice(&crc.sum().to_le_bytes());
+            output.extend_from_slice(&(part.len() as u32).to_le_bytes());
+        }
+
+        Ok(output)
+    }
+
+    #[test]
+    fn header() -> error::Result<()> {
+        let data = compress(b"ACGT\n", 100)?;
+
+        assert!(is_bgzf(&data));
+        assert_eq!(block_length(&data), Some(HEADER_LENGTH + 7 + FOOTER_LENGTH));
+        assert!(!is_bgzf(b"\x1f\x8b\x08\x00\x00\x00\x00\x00\x00\xff"));
+        assert!(!is_bgzf(b"ACGT\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn same_as_plain() -> error::Result<()> {
+        let file = crate::tests::generate_fastq(42, 100, 50)?;
+        let data = std::fs::read(file.path())?;
+        let compressed = tempfile::NamedTempFile::new()?;
+        compressed.as_file().write_all(&compress(&data, 700)?)?;
+
+        let mut plain = LineProducer::with_blocksize(1000, file.path())?;
+        let mut bgzf = LineProducer::with_blocksize(1000, compressed.path())?;
+
+        while let Some(block) = plain.next_block()? {
+            let other = bgzf.next_block()?.unwrap();
+
+            assert_eq!(block.data(), other.data());
+            assert_eq!(block.offset(), other.offset());
+        }
+        assert!(bgzf.next_block()?.is_none());
+
+        let mut reader =
+            Reader::with_batch(3, unsafe { memmap2::Mmap::map(compressed.as_file())? });
+        let mut inflated = Vec::new();
+        reader.read_to_end(&mut inflated)?;
+
+        assert_eq!(inflated, data);
+        assert_eq!(reader.position(), compressed.as_file().metadata()?.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn corrupted() -> error::Result<()> {
+        let mut data = compress(b"ACGT\nACGT\n", 5)?;
+        let length = data.len();
+        data[length - 40] ^= 0xff;
+
+        let file = tempfile::NamedTempFile::new()?;
+        file.as_file().write_all(&data)?;
+
+        let mut reader = Reader::new(unsafe { memmap2::Mmap::map(file.as_file())? });
+
+        assert!(reader.read_to_end(&mut Vec::new()).is_err());
+
+        let mut data = compress(b"ACGT\nACGT\n", 5)?;
+        let end = block_length(&data).unwrap();
+        data[end - 4..end].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        let file = tempfile::NamedTempFile::new()?;
+        file.as_file().write_all(&data)?;
+
+        let mut reader = Reader::new(unsafe { memmap2::Mmap::map(file.as_file())? });
+
+        assert!(reader.read_to_end(&mut Vec::new()).is_err());
+
+        Ok(())
+    }
+}
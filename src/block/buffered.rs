@@ -0,0 +1,296 @@
+//! Struct that extract block from a non-seekable input (stdin, pipe, FIFO) by reading it in owned buffer.
+
+/* std use */
+use std::io::Read as _;
+
+/* crate use */
+
+/* project use */
+use crate::block;
+use crate::error;
+
+/// Buffer that read an input and split it in [Block](block::Block)
+pub(crate) struct Buffer<R> {
+    reader: R,
+    buffer: Vec<u8>,
+    offset: u64,
+    block_index: u64,
+    eof: bool,
+}
+
+impl<R> Buffer<R>
+where
+    R: std::io::Read,
+{
+    /// Create a new buffer
+    pub(crate) fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buffer: Vec::new(),
+            offset: 0,
+            block_index: 0,
+            eof: false,
+        }
+    }
+
+    /// Get next block, block limit is found by correct_block_size like in [BlockProducer::next_block](block::BlockProducer::next_block)
+    pub(crate) fn next_block(
+        &mut self,
+        blocksize: u64,
+        max_blocksize: u64,
+        parse_mode: block::ParseMode,
+        correct_block_size: fn(&[u8]) -> error::Result<u64>,
+    ) -> error::Result<Option<block::Block>> {
+        let mut length = blocksize.max(1);
+
+        loop {
+            self.fill(length)?;
+
+            if self.buffer.is_empty() {
+                return Ok(None);
+            } else if self.eof {
+                let block = std::mem::take(&mut self.buffer);
+
+                return Ok(Some(self.emit(block)));
+            }
+
+            let cut = block::cut_block(
+                &self.buffer,
+                max_blocksize,
+                parse_mode,
+                correct_block_size,
+                || {
+                    let mut block = block::Block::from_buffer(
+                        0..self.buffer.len(),
+                        std::sync::Arc::new(self.buffer.clone()),
+                    );
+                    block.set_offset(self.offset);
+                    block.set_index(self.block_index);
+                    block
+                },
+            )?;
+            let blocksize = match cut {
+                block::Cut::Size(blocksize) => blocksize,
+                block::Cut::Grow(next) => {
+                    length = next;
+                    continue;
+                }
+            };
+
+            let rest = self.buffer.split_off(blocksize as usize);
+            let block = std::mem::replace(&mut self.buffer, rest);
+
+            return Ok(Some(self.emit(block)));
+        }
+    }
+
+    /// Drop length bytes of input
+    #[cfg(feature = "gzip")]
+    pub(crate) fn skip(&mut self, length: u64) -> error::Result<()> {
+        let skipped = std::io::copy(&mut self.reader.by_ref().take(length), &mut std::io::sink())?;
+
+        self.offset += skipped;
+        self.eof = skipped < length;
+
+        Ok(())
+    }
+
+    /// Read input until buffer reach length or input end
+    fn fill(&mut self, length: u64) -> error::Result<()> {
+        let missing = length.saturating_sub(self.buffer.len() as u64);
+
+        if !self.eof && missing != 0 {
+            let read = self
+                .reader
+                .by_ref()
+                .take(missing)
+                .read_to_end(&mut self.buffer)?;
+
+            self.eof = (read as u64) < missing;
+        }
+
+        Ok(())
+    }
+
+    /// Build block from buffer
+    fn emit(&mut self, buffer: Vec<u8>) -> block::Block {
+        let mut block = block::Block::from_buffer(0..buffer.len(), std::sync::Arc::new(buffer));
+
+        block.set_offset(self.offset);
+        block.set_index(self.block_index);
+
+        self.offset += block.len() as u64;
+        self.block_index += 1;
+
+        block
+    }
+
+    /// Get number of bytes already produce
+    pub(crate) fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+impl<R> std::fmt::Debug for Buffer<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Buffer")
+            .field("buffer_length", &self.buffer.len())
+            .field("offset", &self.offset)
+            .field("block_index", &self.block_index)
+            .field("eof", &self.eof)
+            .finish()
+    }
+}
+
+/// Producer that read [Block](block::Block) from a [std::io::Read]
+///
+/// Block limits are found by `correct_block_size` of producer `P`, bytes after last complete record are keep for next block.
+pub struct Producer<P, R> {
+    buffer: Buffer<R>,
+    blocksize: u64,
+    max_blocksize: u64,
+    parse_mode: block::ParseMode,
+    format: std::marker::PhantomData<P>,
+}
+
+impl<P, R> Producer<P, R>
+where
+    P: block::BlockProducer,
+    R: std::io::Read,
+{
+    /// Create a new buffered producer
+    pub fn new(reader: R) -> Self {
+        Self::with_blocksize(crate::DEFAULT_BLOCKSIZE, reader)
+    }
+
+    /// Create a new buffered producer with a blocksize choose by user
+    pub fn with_blocksize(blocksize: u64, reader: R) -> Self {
+        Self {
+            buffer: Buffer::new(reader),
+            blocksize: blocksize.max(1),
+            max_blocksize: crate::DEFAULT_MAX_BLOCKSIZE,
+            parse_mode: block::ParseMode::default(),
+            format: std::marker::PhantomData,
+        }
+    }
+
+    /// Get next block, block limits are found like in [BlockProducer::next_block](block::BlockProducer::next_block)
+    pub fn next_block(&mut self) -> error::Result<Option<block::Block>> {
+        self.buffer.next_block(
+            self.blocksize,
+            self.max_blocksize,
+            self.parse_mode,
+            P::correct_block_size,
+        )
+    }
+
+    /// Get number of bytes already produce
+    pub fn offset(&self) -> u64 {
+        self.buffer.offset()
+    }
+
+    /// Get blocksize
+    pub fn blocksize(&self) -> u64 {
+        self.blocksize
+    }
+
+    /// Get maximal size a block can grow to contain a complete record
+    pub fn max_blocksize(&self) -> u64 {
+        self.max_blocksize
+    }
+
+    /// Set maximal size a block can grow, see [BlockProducer::set_max_blocksize](block::BlockProducer::set_max_blocksize)
+    pub fn set_max_blocksize(&mut self, value: u64) {
+        self.max_blocksize = value;
+    }
+
+    /// Get how malformed records are handled
+    pub fn parse_mode(&self) -> block::ParseMode {
+        self.parse_mode
+    }
+
+    /// Set how malformed records are handled, see [BlockProducer::set_parse_mode](block::BlockProducer::set_parse_mode)
+    pub fn set_parse_mode(&mut self, mode: block::ParseMode) {
+        self.parse_mode = mode;
+    }
+}
+
+impl<P, R> Iterator for Producer<P, R>
+where
+    P: block::BlockProducer,
+    R: std::io::Read,
+{
+    type Item = error::Result<block::Block>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_block().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::block::tests::LineProducer;
+    use crate::block::BlockProducer as _;
+
+    /// A reader that return few bytes at each call like a pipe
+    struct Pipe<'a> {
+        data: &'a [u8],
+    }
+
+    impl<'a> std::io::Read for Pipe<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let length = buf.len().min(self.data.len()).min(7);
+
+            buf[..length].copy_from_slice(&self.data[..length]);
+            self.data = &self.data[length..];
+
+            Ok(length)
+        }
+    }
+
+    #[test]
+    fn same_as_file() -> error::Result<()> {
+        let file = crate::tests::generate_fastq(42, 100, 50)?;
+        let data = std::fs::read(file.path())?;
+
+        let mut mapped = LineProducer::with_blocksize(1000, file.path())?;
+        let mut buffered = Producer::<LineProducer, _>::with_blocksize(1000, Pipe { data: &data });
+
+        while let Some(block) = mapped.next_block()? {
+            let other = buffered.next_block()?.unwrap();
+
+            assert_eq!(block.data(), other.data());
+            assert_eq!(block.offset(), other.offset());
+            assert_eq!(block.index(), other.index());
+        }
+
+        assert!(buffered.next_block()?.is_none());
+        assert_eq!(buffered.offset(), data.len() as u64);
+
+        Ok(())
+    }
+
+    #[test]
+    fn record_larger_than_blocksize() -> error::Result<()> {
+        let data = b"a short line\na very long line, longer than blocksize\nend\n";
+
+        let mut producer = Producer::<LineProducer, _>::with_blocksize(16, Pipe { data });
+
+        assert_eq!(producer.next_block()?.unwrap().data(), b"a short line\n");
+        assert_eq!(
+            producer.next_block()?.unwrap().data(),
+            b"a very long line, longer than blocksize\nend\n"
+        );
+        assert!(producer.next_block()?.is_none());
+
+        let mut producer = Producer::<LineProducer, _>::with_blocksize(16, Pipe { data });
+        producer.set_max_blocksize(16);
+
+        assert!(producer.next_block().is_ok());
+        assert!(producer.next_block().is_err());
+
+        Ok(())
+    }
+}
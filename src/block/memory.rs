@@ -0,0 +1,255 @@
+//! Struct that extract block from data already in memory (byte slice, vector, embedded reference).
+
+/* std use */
+
+/* crate use */
+
+/* project use */
+use crate::block;
+use crate::error;
+
+/// Producer that split in memory data in [Block](block::Block)
+///
+/// Data is shared between all blocks without copy, block limits are found by `correct_block_size` of producer `P`. Data could be `&'static [u8]` (like `include_bytes!`), `Vec<u8>`, `Arc<Vec<u8>>` or `Arc<[u8]>`.
+pub struct Producer<P> {
+    data: block::Block,
+    offset: u64,
+    blocksize: u64,
+    max_blocksize: u64,
+    block_index: u64,
+    parse_mode: block::ParseMode,
+    format: std::marker::PhantomData<P>,
+}
+
+impl<P> Producer<P>
+where
+    P: block::BlockProducer,
+{
+    /// Create a new in memory producer
+    pub fn new<D>(data: D) -> Self
+    where
+        D: Into<block::Block>,
+    {
+        Self::with_blocksize(crate::DEFAULT_BLOCKSIZE, data)
+    }
+
+    /// Create a new in memory producer with a blocksize choose by user
+    pub fn with_blocksize<D>(blocksize: u64, data: D) -> Self
+    where
+        D: Into<block::Block>,
+    {
+        let data = data.into();
+
+        Self {
+            blocksize: (data.len() as u64).min(blocksize),
+            data,
+            offset: 0,
+            max_blocksize: crate::DEFAULT_MAX_BLOCKSIZE,
+            block_index: 0,
+            parse_mode: block::ParseMode::default(),
+            format: std::marker::PhantomData,
+        }
+    }
+
+    /// Get next block, block limits are found like in [BlockProducer::next_block](block::BlockProducer::next_block)
+    pub fn next_block(&mut self) -> error::Result<Option<block::Block>> {
+        let end = self.data.len() as u64;
+        let mut length = self.blocksize;
+
+        loop {
+            if self.offset >= end {
+                return Ok(None);
+            } else if self.offset + length >= end {
+                return Ok(Some(self.emit(end - self.offset)));
+            }
+
+            let range = self.offset as usize..(self.offset + length) as usize;
+            let cut = block::cut_block(
+                &self.data.data()[range.clone()],
+                self.max_blocksize,
+                self.parse_mode,
+                P::correct_block_size,
+                || {
+                    let mut block = self.data.slice(range);
+                    block.set_index(self.block_index);
+                    block
+                },
+            )?;
+
+            let blocksize = match cut {
+                block::Cut::Size(blocksize) => blocksize,
+                block::Cut::Grow(next) => {
+                    length = next;
+                    continue;
+                }
+            };
+
+            return Ok(Some(self.emit(blocksize)));
+        }
+    }
+
+    /// Build block of length bytes at current offset
+    fn emit(&mut self, length: u64) -> block::Block {
+        let range = self.offset as usize..(self.offset + length) as usize;
+        let mut block = self.data.slice(range);
+
+        block.set_index(self.block_index);
+
+        self.offset += length;
+        self.block_index += 1;
+
+        block
+    }
+
+    /// Get data
+    pub fn data(&self) -> &[u8] {
+        self.data.data()
+    }
+
+    /// Get current offset
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Set current offset
+    pub fn set_offset(&mut self, value: u64) {
+        self.offset = value;
+    }
+
+    /// Get blocksize
+    pub fn blocksize(&self) -> u64 {
+        self.blocksize
+    }
+
+    /// Get maximal size a block can grow to contain a complete record
+    pub fn max_blocksize(&self) -> u64 {
+        self.max_blocksize
+    }
+
+    /// Set maximal size a block can grow, see [BlockProducer::set_max_blocksize](block::BlockProducer::set_max_blocksize)
+    pub fn set_max_blocksize(&mut self, value: u64) {
+        self.max_blocksize = value;
+    }
+
+    /// Get how malformed records are handled
+    pub fn parse_mode(&self) -> block::ParseMode {
+        self.parse_mode
+    }
+
+    /// Set how malformed records are handled, see [BlockProducer::set_parse_mode](block::BlockProducer::set_parse_mode)
+    pub fn set_parse_mode(&mut self, mode: block::ParseMode) {
+        self.parse_mode = mode;
+    }
+}
+
+impl<P> std::fmt::Debug for Producer<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Producer")
+            .field("data_length", &self.data.len())
+            .field("offset", &self.offset)
+            .field("blocksize", &self.blocksize)
+            .field("max_blocksize", &self.max_blocksize)
+            .field("block_index", &self.block_index)
+            .finish()
+    }
+}
+
+impl<P> Iterator for Producer<P>
+where
+    P: block::BlockProducer,
+{
+    type Item = error::Result<block::Block>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_block().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::block::tests::LineProducer;
+    use crate::block::BlockProducer as _;
+
+    #[test]
+    fn same_as_file() -> error::Result<()> {
+        let file = crate::tests::generate_fastq(42, 100, 50)?;
+        let data = std::fs::read(file.path())?;
+
+        let mut mapped = LineProducer::with_blocksize(1000, file.path())?;
+        let mut memory = Producer::<LineProducer>::with_blocksize(1000, data.clone());
+
+        while let Some(block) = mapped.next_block()? {
+            let other = memory.next_block()?.unwrap();
+
+            assert_eq!(block.data(), other.data());
+            assert_eq!(block.offset(), other.offset());
+            assert_eq!(block.index(), other.index());
+        }
+
+        assert!(memory.next_block()?.is_none());
+        assert_eq!(memory.offset(), data.len() as u64);
+
+        Ok(())
+    }
+
+    #[test]
+    fn no_copy() -> error::Result<()> {
+        let data: &'static [u8] = b"a short line\na very long line\n";
+        let block = Producer::<LineProducer>::with_blocksize(16, data)
+            .next_block()?
+            .unwrap();
+        assert_eq!(block.data().as_ptr(), data.as_ptr());
+
+        let vector = data.to_vec();
+        let pointer = vector.as_ptr();
+        let block = Producer::<LineProducer>::with_blocksize(16, vector)
+            .next_block()?
+            .unwrap();
+        assert_eq!(block.data().as_ptr(), pointer);
+
+        Ok(())
+    }
+
+    #[test]
+    fn sources() -> error::Result<()> {
+        let data: &[u8] = b"a short line\na very long line, longer than blocksize\nend\n";
+
+        let slice = Producer::<LineProducer>::with_blocksize(16, data);
+        let vector = Producer::<LineProducer>::with_blocksize(16, data.to_vec());
+        let embedded = Producer::<LineProducer>::with_blocksize(16, b"a short line\n");
+        let shared =
+            Producer::<LineProducer>::with_blocksize(16, std::sync::Arc::<[u8]>::from(data));
+
+        for producer in [slice, vector, shared] {
+            let blocks = producer.collect::<error::Result<Vec<block::Block>>>()?;
+
+            assert_eq!(
+                blocks
+                    .iter()
+                    .map(block::Block::data)
+                    .collect::<Vec<&[u8]>>(),
+                vec![
+                    &b"a short line\n"[..],
+                    &b"a very long line, longer than blocksize\nend\n"[..],
+                ]
+            );
+        }
+
+        assert_eq!(
+            embedded
+                .map(|block| block.map(|block| block.data().to_vec()))
+                .collect::<error::Result<Vec<Vec<u8>>>>()?,
+            vec![b"a short line\n".to_vec()]
+        );
+
+        let mut producer = Producer::<LineProducer>::with_blocksize(16, data);
+        producer.set_max_blocksize(16);
+
+        assert!(producer.next_block().is_ok());
+        assert!(producer.next_block().is_err());
+
+        Ok(())
+    }
+}
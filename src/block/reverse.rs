@@ -0,0 +1,175 @@
+//! Struct that extract block from the end of file to the begin.
+
+/* std use */
+
+/* crate use */
+
+/* project use */
+use crate::block;
+use crate::error;
+
+/// Producer that walk [Block](block::Block) from end of file to begin
+///
+/// Begin of each block is found by [find_record_start](block::BlockProducer::find_record_start) of producer `P`, so only blocks at end of file are mapped. Blocks are produce in reverse order but records in a block keep file order, first block produce get index 0.
+pub struct Producer<P> {
+    inner: P,
+    end: u64,
+}
+
+impl<P> Producer<P>
+where
+    P: block::BlockProducer,
+{
+    /// Create a new reverse producer
+    pub fn new<T>(path: T) -> error::Result<Self>
+    where
+        T: AsRef<std::path::Path>,
+    {
+        Self::with_blocksize(crate::DEFAULT_BLOCKSIZE, path)
+    }
+
+    /// Create a new reverse producer with a blocksize choose by user
+    pub fn with_blocksize<T>(blocksize: u64, path: T) -> error::Result<Self>
+    where
+        T: AsRef<std::path::Path>,
+    {
+        Ok(Self::from_producer(P::with_blocksize(blocksize, path)?))
+    }
+
+    /// Create a reverse producer that walk backward between offset and end of a producer
+    pub fn from_producer(inner: P) -> Self {
+        Self {
+            end: inner.end(),
+            inner,
+        }
+    }
+
+    /// Get previous block
+    ///
+    /// If no record begin in blocksize bytes before previous block, window size is doubled until a record begin is found or [max_blocksize](block::BlockProducer::max_blocksize) is reach.
+    pub fn next_block(&mut self) -> error::Result<Option<block::Block>> {
+        let begin = self.inner.offset();
+        let mut length = self.inner.blocksize().max(1);
+
+        loop {
+            if self.end <= begin {
+                return Ok(None);
+            }
+
+            let from = self.end.saturating_sub(length).max(begin);
+            let start = if from == begin {
+                begin
+            } else {
+                self.inner.next_record_start(from)?
+            };
+
+            if start < self.end {
+                let block = self.inner.state().map(start, self.end - start)?;
+                self.end = start;
+
+                return Ok(Some(self.inner.state_mut().emit(block)));
+            } else if length >= self.inner.max_blocksize() {
+                let window = self.inner.state().map(from, self.end - from)?;
+
+                return Err(block::locate(error::Error::NoRecordStart, &window, 0));
+            }
+
+            length = (length * 2).min(self.inner.max_blocksize());
+            log::debug!(
+                "No record begin before offset {} grow window to {}",
+                self.end,
+                length
+            );
+        }
+    }
+
+    /// Get offset of begin of last produced block
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+
+    /// Get producer used to map file
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Get mutable access to producer used to map file
+    pub fn inner_mut(&mut self) -> &mut P {
+        &mut self.inner
+    }
+}
+
+impl<P> Iterator for Producer<P>
+where
+    P: block::BlockProducer,
+{
+    type Item = error::Result<block::Block>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_block().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::block::tests::LineProducer;
+    use crate::block::BlockProducer as _;
+
+    #[test]
+    fn same_data_as_forward() -> error::Result<()> {
+        let file = crate::tests::generate_fastq(42, 100, 50)?;
+        let data = std::fs::read(file.path())?;
+
+        let mut producer = Producer::<LineProducer>::with_blocksize(1000, file.path())?;
+
+        let mut blocks = Vec::new();
+        while let Some(block) = producer.next_block()? {
+            assert_eq!(block.index(), blocks.len() as u64);
+            assert!(block.len() <= 1000);
+            assert_eq!(block.data().last(), Some(&b'\n'));
+
+            blocks.push(block);
+        }
+
+        let mut offset = 0;
+        let mut reversed = Vec::new();
+        for block in blocks.iter().rev() {
+            assert_eq!(block.offset(), offset);
+            offset += block.len() as u64;
+
+            reversed.extend_from_slice(block.data());
+        }
+
+        assert_eq!(reversed, data);
+
+        Ok(())
+    }
+
+    #[test]
+    fn record_larger_than_blocksize() -> error::Result<()> {
+        let file = tempfile::NamedTempFile::new()?;
+        std::fs::write(
+            file.path(),
+            b"begin\na very long line, longer than blocksize\nend\n",
+        )?;
+
+        let mut producer = Producer::<LineProducer>::with_blocksize(8, file.path())?;
+
+        assert_eq!(producer.next_block()?.unwrap().data(), b"end\n");
+        assert_eq!(
+            producer.next_block()?.unwrap().data(),
+            b"begin\na very long line, longer than blocksize\n"
+        );
+        assert!(producer.next_block()?.is_none());
+
+        let mut producer = Producer::<LineProducer>::with_blocksize(8, file.path())?;
+        producer.inner_mut().set_max_blocksize(8);
+
+        assert!(producer.next_block().is_ok());
+        assert!(producer.next_block().is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+//! Struct that extract a sample of blocks spread across a file.
+
+/* std use */
+
+/* crate use */
+
+/* project use */
+use crate::block;
+use crate::error;
+
+/// Producer that map a fixed number of randomly chosen [Block](block::Block)
+///
+/// File is split in `count` strata of same size, a random position is drawn in each strata and the block begin at the first record after this position. Block end is found by [correct_block_size](block::BlockProducer::correct_block_size) of producer `P`, blocks never overlap and are produce in file order. Same seed give same blocks.
+pub struct Producer<P> {
+    inner: P,
+    positions: std::vec::IntoIter<u64>,
+    consumed: u64,
+}
+
+impl<P> Producer<P>
+where
+    P: block::BlockProducer,
+{
+    /// Create a new sampling producer that produce count blocks
+    pub fn new<T>(count: u64, seed: u64, path: T) -> error::Result<Self>
+    where
+        T: AsRef<std::path::Path>,
+    {
+        Self::with_blocksize(crate::DEFAULT_BLOCKSIZE, count, seed, path)
+    }
+
+    /// Create a new sampling producer that produce count blocks with a blocksize choose by user
+    pub fn with_blocksize<T>(blocksize: u64, count: u64, seed: u64, path: T) -> error::Result<Self>
+    where
+        T: AsRef<std::path::Path>,
+    {
+        Ok(Self::from_producer(
+            P::with_blocksize(blocksize, path)?,
+            count,
+            seed,
+        ))
+    }
+
+    /// Create a sampling producer that draw count blocks between offset and end of a producer
+    pub fn from_producer(inner: P, count: u64, seed: u64) -> Self {
+        let begin = inner.offset();
+        let length = inner.end().saturating_sub(begin);
+        let count = count.min(length);
+
+        let mut rng = SplitMix64(seed);
+        let positions = (0..count)
+            .map(|i| {
+                let low = begin + length * i / count;
+                let high = begin + length * (i + 1) / count;
+
+                low + rng.next() % (high - low)
+            })
+            .collect::<Vec<u64>>();
+
+        Self {
+            consumed: begin,
+            inner,
+            positions: positions.into_iter(),
+        }
+    }
+
+    /// Get next sampled block
+    pub fn next_block(&mut self) -> error::Result<Option<block::Block>> {
+        for position in self.positions.by_ref() {
+            let start = self.inner.next_record_start(position.max(self.consumed))?;
+            if start >= self.inner.end() {
+                return Ok(None);
+            }
+
+            self.inner.set_offset(start);
+            if let Some(block) = self.inner.next_block()? {
+                self.consumed = self.inner.offset();
+                return Ok(Some(block));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Get producer used to map file
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Get mutable access to producer used to map file
+    pub fn inner_mut(&mut self) -> &mut P {
+        &mut self.inner
+    }
+}
+
+impl<P> Iterator for Producer<P>
+where
+    P: block::BlockProducer,
+{
+    type Item = error::Result<block::Block>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_block().transpose()
+    }
+}
+
+/// Small pseudo random generator, enough to draw positions
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::block::tests::LineProducer;
+
+    #[test]
+    fn sample() -> error::Result<()> {
+        let file = crate::tests::generate_fastq(42, 1_000, 50)?;
+        let data = std::fs::read(file.path())?;
+
+        let blocks = Producer::<LineProducer>::with_blocksize(500, 10, 42, file.path())?
+            .collect::<error::Result<Vec<block::Block>>>()?;
+
+        assert_eq!(blocks.len(), 10);
+        for block in blocks.iter() {
+            let offset = block.offset() as usize;
+
+            assert!(offset == 0 || data[offset - 1] == b'\n');
+            assert_eq!(block.data(), &data[offset..offset + block.len()]);
+            assert_eq!(block.data().last(), Some(&b'\n'));
+        }
+        assert!(blocks
+            .windows(2)
+            .all(|pair| pair[0].offset() + pair[0].len() as u64 <= pair[1].offset()));
+
+        let again = Producer::<LineProducer>::with_blocksize(500, 10, 42, file.path())?
+            .collect::<error::Result<Vec<block::Block>>>()?;
+        assert_eq!(
+            blocks
+                .iter()
+                .map(block::Block::offset)
+                .collect::<Vec<u64>>(),
+            again.iter().map(block::Block::offset).collect::<Vec<u64>>()
+        );
+
+        let other = Producer::<LineProducer>::with_blocksize(500, 10, 43, file.path())?
+            .collect::<error::Result<Vec<block::Block>>>()?;
+        assert_ne!(
+            blocks
+                .iter()
+                .map(block::Block::offset)
+                .collect::<Vec<u64>>(),
+            other.iter().map(block::Block::offset).collect::<Vec<u64>>()
+        );
+
+        Ok(())
+    }
+}
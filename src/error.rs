@@ -27,6 +27,24 @@ pub enum Error {
         source: std::io::Error,
     },
 
+    /// File was modified or truncated since it was opened
+    #[error("biommap detect file {path} was modified since it was opened")]
+    FileChanged {
+        /// Path of file
+        path: std::path::PathBuf,
+    },
+
+    /// Input can't be map in memory
+    #[error("biommap can't map a compressed or streamed input, this operation isn't available")]
+    NotMappable,
+
+    /// BGZF block is truncated or corrupted
+    #[error("biommap found an invalid BGZF block at offset {offset}")]
+    InvalidBgzfBlock {
+        /// Offset of block in compressed file
+        offset: u64,
+    },
+
     /// Generic io::Error
     #[error(transparent)]
     IO(#[from] std::io::Error),
@@ -35,10 +53,22 @@ pub enum Error {
     #[error("biommap didn't find new line in block increase block size")]
     NoNewLineInBlock,
 
+    /// biommap didn't find a record begin before reach maximal block size
+    #[error("biommap didn't find a record begin increase maximal block size")]
+    NoRecordStart,
+
+    /// Many position could be a record begin
+    #[error("biommap can't determine where a record begin")]
+    AmbiguousRecordStart,
+
     /// File seems not containts fastq data
     #[error("Input file seems not be a fastq file")]
     NotAFastqFile,
 
+    /// Fastq error
+    #[error(transparent)]
+    FastqError(#[from] FastqError),
+
     /// File seems not containts fasta data
     #[error("Input file seems not be a fasta file")]
     NotAFastaFile,
@@ -54,6 +84,85 @@ pub enum Error {
     /// Current record seems to be a partial record
     #[error("biommap found a partial record")]
     PartialRecord,
+
+    /// Error with its location in input
+    #[error("{source} in {} at byte {offset}, line {line} of block {block} at byte {block_offset}: \"{excerpt}\"", input_name(.path))]
+    Located {
+        /// Original error
+        source: Box<Error>,
+
+        /// Path of input, None if input isn't a file
+        path: Option<std::path::PathBuf>,
+
+        /// Absolute offset of error in input
+        offset: u64,
+
+        /// Index of block that contains error
+        block: u64,
+
+        /// Absolute offset of block that contains error
+        block_offset: u64,
+
+        /// Line number of error in its block, first line of block is 1
+        line: u64,
+
+        /// Escaped bytes of input at offset
+        excerpt: String,
+    },
+}
+
+impl Error {
+    /// Get error without location
+    pub fn root(&self) -> &Error {
+        match self {
+            Error::Located { source, .. } => source.root(),
+            other => other,
+        }
+    }
+}
+
+/// Name of input in error message
+fn input_name(path: &Option<std::path::PathBuf>) -> String {
+    match path {
+        Some(path) => path.display().to_string(),
+        None => "input".to_string(),
+    }
+}
+
+/// Enum to manage fastq validation error
+#[derive(std::fmt::Debug, thiserror::Error)]
+pub enum FastqError {
+    /// Header line didn't begin by `@`
+    #[error("Fastq header line didn't begin by @")]
+    HeaderPrefix,
+
+    /// Plus line didn't begin by `+`
+    #[error("Fastq plus line didn't begin by +")]
+    PlusPrefix,
+
+    /// Plus line contains a name different of header name
+    #[error("Fastq plus line didn't repeat header name")]
+    PlusName,
+
+    /// Sequence and quality haven't same length
+    #[error("Fastq sequence length {sequence} and quality length {quality} are different")]
+    LengthMismatch {
+        /// Length of sequence
+        sequence: usize,
+
+        /// Length of quality
+        quality: usize,
+    },
+
+    /// A quality character is outside of `!` to `~` range
+    #[error("Fastq quality character {value} at position {position} is out of range")]
+    QualityRange {
+        /// Position of character in quality
+        position: usize,
+
+        /// Value of character
+        value: u8,
+    },
 }
 
 /// Enum to manage vcf error
@@ -1,16 +1,12 @@
 //! Struct that extract part of file (called block) and read it as fasta file.
 
-// #![feature(trace_macros)]
-
-// trace_macros!(true);
-
 /* crate use */
 use bstr::ByteSlice;
 
 /* project use */
 use crate::block;
+use crate::block::BlockProducer as _;
 use crate::error;
-use crate::impl_producer;
 use crate::impl_reader;
 
 /// Struct that store a fasta record
@@ -20,43 +16,426 @@ pub struct Record<'a> {
 
     /// Fasta sequence
     pub sequence: &'a [u8],
+
+    origin: block::Origin<'a>,
 }
 
-impl_producer!(Producer, |block: &[u8]| {
-    let mut end = block.len();
+impl<'a> Record<'a> {
+    /// Get origin of record
+    pub fn origin(&self) -> block::Origin<'a> {
+        self.origin
+    }
+
+    /// Get absolute offset of record in file
+    pub fn file_offset(&self) -> u64 {
+        self.origin.file_offset
+    }
+
+    /// Copy record in an owned record
+    pub fn to_owned(&self) -> OwnedRecord {
+        let mut owned = OwnedRecord::default();
+        self.copy_to(&mut owned);
+
+        owned
+    }
+
+    /// Copy record in an owned record, memory of owned record is reused
+    pub fn copy_to(&self, owned: &mut OwnedRecord) {
+        owned.comment.clear();
+        owned.comment.extend_from_slice(self.comment);
+        owned.sequence.clear();
+        owned.sequence.extend_from_slice(self.sequence);
+        owned.file_offset = self.origin.file_offset;
+    }
+}
+
+/// Struct that store a fasta record that own its data
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OwnedRecord {
+    /// Fasta comment without `>`
+    pub comment: Vec<u8>,
+
+    /// Fasta sequence
+    pub sequence: Vec<u8>,
+
+    file_offset: u64,
+}
+
+impl OwnedRecord {
+    /// Get a record that borrow data of owned record
+    pub fn as_ref(&self) -> Record<'_> {
+        Record {
+            comment: &self.comment,
+            sequence: &self.sequence,
+            origin: block::Origin {
+                file_offset: self.file_offset,
+                ..block::Origin::default()
+            },
+        }
+    }
+
+    /// Get absolute offset of record in file
+    pub fn file_offset(&self) -> u64 {
+        self.file_offset
+    }
+}
+
+/// Struct that store a mutable fasta record, length of fields can't change to keep block layout
+pub struct RecordMut<'a> {
+    /// Fasta comment without `>`
+    pub comment: &'a mut [u8],
+
+    /// Fasta sequence
+    pub sequence: &'a mut [u8],
+
+    file_offset: u64,
+}
+
+impl<'a> RecordMut<'a> {
+    /// Get absolute offset of record in file
+    pub fn file_offset(&self) -> u64 {
+        self.file_offset
+    }
+}
 
-    for _ in 0..2 {
-        end = block[..end]
-            .rfind_byte(b'\n')
-            .ok_or(error::Error::NoNewLineInBlock)?;
+/// A piece of sequence of a fasta record
+pub struct SequenceChunk {
+    /// Index of record in file, first record is 0
+    pub record_id: u64,
 
-        if end + 1 < block.len() && block[end + 1] == b'>' {
+    /// Position of first base of chunk in sequence of record
+    pub offset_in_record: u64,
+
+    /// Block that contains bases of chunk, it share file mapping if bases are on one line, in other case bases are copied without line terminators
+    pub bases: block::Block,
+}
+
+/// Fasta block producer
+pub struct Producer {
+    state: block::ProducerState,
+}
+
+impl block::BlockProducer for Producer {
+    fn from_state(state: block::ProducerState) -> Self {
+        Self { state }
+    }
+
+    fn state(&self) -> &block::ProducerState {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut block::ProducerState {
+        &mut self.state
+    }
+
+    fn correct_block_size(block: &[u8]) -> error::Result<u64> {
+        // block end before last header
+        if let Some(end) = block.rfind(b"\n>") {
             return Ok((end + 1) as u64);
         }
+
+        // no header after first line, block contains only a part of a record, it must grow
+        match block.iter().find(|c| !matches!(c, b'\n' | b'\r')) {
+            Some(b'>') | None => Err(error::Error::NoNewLineInBlock),
+            Some(_) => Err(error::Error::NotAFastaFile),
+        }
+    }
+
+    fn find_record_start(data: &[u8], from: usize) -> error::Result<Option<usize>> {
+        Ok(block::line_starts(data, from).find(|pos| data[*pos] == b'>'))
     }
+}
 
-    Err(error::Error::NotAFastaFile)
-});
+impl Iterator for Producer {
+    type Item = error::Result<block::Block>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_block().transpose()
+    }
+}
 
 impl_reader!(
     Reader,
     'a,
-    Record,
-    |block: &'a block::Block, offset: &mut usize| {
+    Record<'a>,
+    (),
+    |block: &'a block::Block, offset: &mut usize, mode: block::ParseMode, _options: &()| {
+        Self::skip_empty_lines(block, offset);
+
         if *offset == block.len() {
             Ok(None)
         } else {
-            let comment = &block.data()[Self::get_line(block, offset)?];
-            *offset += comment.len() + 1;
+            let origin = block.origin_at(*offset);
 
-            let sequence = &block.data()[Self::get_line(block, offset)?];
-            *offset += sequence.len() + 1;
+            let comment = Self::next_line(block, offset)?;
+            let sequence = Self::next_line(block, offset)?;
 
-            Ok(Some(Record { comment, sequence }))
+            if mode == block::ParseMode::Lenient
+                && (comment.first() != Some(&b'>') || sequence.first() == Some(&b'>'))
+            {
+                return Err(error::Error::NotAFastaFile);
+            }
+
+            Ok(Some(Record {
+                comment,
+                sequence,
+                origin,
+            }))
         }
+    },
+    Self::skip_empty_lines,
+    |data: &[u8], from: usize| {
+        block::line_starts(data, from)
+            .find(|pos| data[*pos] == b'>')
+            .unwrap_or(data.len())
     }
 );
 
+impl Reader {
+    /// Copy next available record in owned, memory of owned record is reused, return false if block is consumed
+    pub fn next_record_into(&mut self, owned: &mut OwnedRecord) -> error::Result<bool> {
+        match self.next_record()? {
+            Some(record) => {
+                record.copy_to(owned);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+/// Reader that give mutable records
+///
+/// Block is edited in place if it's the only owner of a private mapping (see [MapOptions::copy_on_write](block::MapOptions::copy_on_write)), in other case block data is copied.
+pub struct ReaderMut {
+    offset: usize,
+    block: block::Block,
+}
+
+impl ReaderMut {
+    /// Create a new ReaderMut
+    pub fn new(block: block::Block) -> Self {
+        Self { offset: 0, block }
+    }
+
+    /// Get the next available record
+    pub fn next_record(&mut self) -> error::Result<Option<RecordMut<'_>>> {
+        Reader::skip_empty_lines(&self.block, &mut self.offset);
+
+        if self.offset == self.block.len() {
+            return Ok(None);
+        }
+
+        let file_offset = self.block.origin_at(self.offset).file_offset;
+
+        let comment = Reader::next_line_range(&self.block, &mut self.offset)?;
+        let sequence = Reader::next_line_range(&self.block, &mut self.offset)?;
+
+        let [comment, sequence] =
+            block::split_ranges_mut(self.block.data_mut(), [comment, sequence]);
+
+        Ok(Some(RecordMut {
+            comment,
+            sequence,
+            file_offset,
+        }))
+    }
+
+    /// Get edited block
+    pub fn into_block(self) -> block::Block {
+        self.block
+    }
+}
+
+/// Producer that split sequence of fasta records in [SequenceChunk]
+///
+/// Each chunk contains chunk_length bases plus overlap bases of next chunk, an overlap of k-1 let k-mer at chunk edges be seen once. Sequence could be wrapped on many lines. After an error producer return no more chunk.
+///
+/// Chunks are cut in blocks of a fasta [Producer], a block grow to contain a whole record so a chromosome is mapped in one block, its pages are loaded only when they are read and mapping is released when all chunks of block are dropped. Bases of a chunk on one line share block mapping, bases of a chunk that cross line breaks are copied in a buffer of chunk_length + overlap bytes.
+pub struct ChunkProducer {
+    producer: Producer,
+    block: Option<block::Block>,
+    position: usize,
+    record_id: u64,
+    current: Option<(usize, u64)>,
+    chunk_length: u64,
+    overlap: u64,
+    failed: bool,
+}
+
+impl ChunkProducer {
+    /// Create a new chunk producer
+    pub fn new<P>(chunk_length: u64, overlap: u64, path: P) -> error::Result<Self>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        Ok(Self::from_producer(
+            chunk_length,
+            overlap,
+            Producer::new(path)?,
+        ))
+    }
+
+    /// Create a new chunk producer that read blocks of producer
+    pub fn from_producer(chunk_length: u64, overlap: u64, producer: Producer) -> Self {
+        Self {
+            producer,
+            block: None,
+            position: 0,
+            record_id: 0,
+            current: None,
+            chunk_length: chunk_length.max(1),
+            overlap,
+            failed: false,
+        }
+    }
+
+    /// Get producer of blocks
+    pub fn producer(&self) -> &Producer {
+        &self.producer
+    }
+
+    /// Get next chunk
+    pub fn next_chunk(&mut self) -> error::Result<Option<SequenceChunk>> {
+        if self.failed {
+            return Ok(None);
+        }
+
+        let chunk = self.read_chunk();
+        self.failed = chunk.is_err();
+
+        chunk
+    }
+
+    /// Read next chunk from current block, next block is read when current block is consumed
+    fn read_chunk(&mut self) -> error::Result<Option<SequenceChunk>> {
+        loop {
+            let block = match &self.block {
+                Some(block) => block,
+                None => match self.producer.next_block()? {
+                    Some(block) => {
+                        self.block = Some(block);
+                        self.position = 0;
+                        continue;
+                    }
+                    None => return Ok(None),
+                },
+            };
+            let data = block.data();
+
+            let (position, offset_in_record) = match self.current {
+                Some(current) => current,
+                None => {
+                    Reader::skip_empty_lines(block, &mut self.position);
+                    if self.position == block.len() {
+                        self.block = None;
+                        continue;
+                    }
+
+                    let start = self.position;
+                    if data[start] != b'>' {
+                        return Err(block::locate(error::Error::NotAFastaFile, block, start));
+                    }
+                    Reader::next_line_range(block, &mut self.position)?;
+
+                    self.record_id += 1;
+                    (self.position, 0)
+                }
+            };
+
+            let start = match next_base(data, position) {
+                Some(start) => start,
+                None => {
+                    self.position = position;
+                    self.current = None;
+                    continue;
+                }
+            };
+
+            let (next, _) = take_bases(data, start, self.chunk_length);
+            let (end, _) = take_bases(data, next, self.overlap);
+
+            if next_base(data, end).is_none() {
+                self.position = end;
+                self.current = None;
+            } else {
+                self.current = Some((next, offset_in_record + self.chunk_length));
+            }
+
+            return Ok(Some(SequenceChunk {
+                record_id: self.record_id - 1,
+                offset_in_record,
+                bases: chunk_bases(block, start..end),
+            }));
+        }
+    }
+}
+
+/// Get position of first base at or after pos, None if sequence end before
+fn next_base(data: &[u8], mut pos: usize) -> Option<usize> {
+    while pos < data.len() && matches!(data[pos], b'\n' | b'\r') {
+        pos += 1;
+    }
+
+    if pos == data.len() || (data[pos] == b'>' && (pos == 0 || data[pos - 1] == b'\n')) {
+        None
+    } else {
+        Some(pos)
+    }
+}
+
+/// Move over count bases of a sequence wrapped on many lines, return position after last base and number of bases read
+fn take_bases(data: &[u8], mut pos: usize, count: u64) -> (usize, u64) {
+    let mut taken = 0;
+
+    while taken < count {
+        pos = match next_base(data, pos) {
+            Some(pos) => pos,
+            None => break,
+        };
+
+        let line_end = memchr::memchr2(b'\n', b'\r', &data[pos..])
+            .map(|end| pos + end)
+            .unwrap_or(data.len());
+        let length = ((line_end - pos) as u64).min(count - taken);
+
+        pos += length as usize;
+        taken += length;
+    }
+
+    (pos, taken)
+}
+
+/// Build block of bases in range, line terminators are removed by a copy only if range contains some
+fn chunk_bases(block: &block::Block, range: std::ops::Range<usize>) -> block::Block {
+    let data = &block.data()[range.clone()];
+
+    if memchr::memchr2(b'\n', b'\r', data).is_none() {
+        return block.slice(range);
+    }
+
+    let bases = data
+        .iter()
+        .filter(|c| !matches!(c, b'\n' | b'\r'))
+        .copied()
+        .collect::<Vec<u8>>();
+
+    let mut chunk = block::Block::from_buffer(0..bases.len(), std::sync::Arc::new(bases));
+    chunk.set_source(block.source_index(), block.path().map(std::sync::Arc::from));
+    chunk.set_offset(block.offset() + range.start as u64);
+    chunk.set_index(block.index());
+
+    chunk
+}
+
+impl Iterator for ChunkProducer {
+    type Item = error::Result<SequenceChunk>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_chunk().transpose()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -189,9 +568,84 @@ CACAGAGCTCGTATAATCAGTAAACGCCACGGTCCTTTCTCTGTTAACCGCTATGCTAGAGTTCGACGGATTGCGAACTG
             Ok(())
         }
 
+        #[test]
+        fn record_larger_than_blocksize() -> error::Result<()> {
+            let file = crate::tests::generate_fasta(42, 10, 1_000)?;
+
+            let mut tmp = Producer::with_blocksize(100, file.path())?;
+
+            let mut block_length = Vec::new();
+            while let Some(block) = tmp.next_block()? {
+                block_length.push(block.len());
+            }
+
+            assert_eq!(block_length, vec![1004; 10]);
+
+            let mut data = Vec::new();
+            for index in 0..4 {
+                data.extend_from_slice(format!(">{}\n", index).as_bytes());
+                for _ in 0..20 {
+                    data.extend_from_slice(
+                        b"ACGTACGTACGTACGTACGTACGTACGTACGTACGTACGTACGTACGTACGTACGTACGT\n",
+                    );
+                }
+            }
+            let wrapped = crate::tests::write_in_tempfile(&data)?;
+
+            let mut blocks = Vec::new();
+            for block in Producer::with_blocksize(100, wrapped.path())? {
+                let block = block?;
+                blocks.push((block.data()[..3].to_vec(), block.len()));
+            }
+
+            assert_eq!(
+                blocks,
+                (0..4)
+                    .map(|index| (format!(">{}\n", index).into_bytes(), 3 + 20 * 61))
+                    .collect::<Vec<(Vec<u8>, usize)>>()
+            );
+
+            let mut tmp = Producer::with_blocksize(100, file.path())?;
+            tmp.set_max_blocksize(800);
+
+            assert!(tmp.next_block().is_err());
+
+            Ok(())
+        }
+
+        #[test]
+        fn with_range() -> error::Result<()> {
+            let file = crate::tests::generate_fasta(42, 1_000, 150)?;
+            let length = Producer::filesize(&file.path())?;
+            let limits = [0, 154, 155, 5_000, 77_777, length - 1, length];
+
+            let mut comments = Vec::new();
+            for range in limits.windows(2) {
+                let mut producer =
+                    Producer::with_blocksize_range(1_000, range[0], range[1], file.path())?;
+
+                while let Some(block) = producer.next_block()? {
+                    let mut reader = Reader::new(block);
+
+                    while let Some(record) = reader.next_record()? {
+                        comments.push(String::from_utf8(record.comment.to_vec()).unwrap());
+                    }
+                }
+            }
+
+            assert_eq!(
+                comments,
+                (0..1_000)
+                    .map(|i| format!(">{}", i))
+                    .collect::<Vec<String>>()
+            );
+
+            Ok(())
+        }
+
         #[test]
         fn not_a_fasta() -> error::Result<()> {
-            let mut file = crate::tests::write_in_tempfile(
+            let file = crate::tests::write_in_tempfile(
                 b"Lorem ipsum dolor sit amet, consectetur adipiscing elit.
 Vivamus ut nulla eget diam eleifend bibendum.
 Praesent porta sapien id tortor hendrerit, a hendrerit dolor commodo. Donec sed elit enim.",
@@ -202,7 +656,7 @@ Praesent porta sapien id tortor hendrerit, a hendrerit dolor commodo. Donec sed
 
             {
                 let mut rewrite = file.reopen()?;
-                rewrite.write(
+                rewrite.write_all(
                     b"+FAILLED FILE
 +3
 +TTGGGCATGAGGTTCA
@@ -230,7 +684,7 @@ Praesent porta sapien id tortor hendrerit, a hendrerit dolor commodo. Donec sed
 
         #[test]
         fn iterate_over_seq() -> error::Result<()> {
-            let mut file = crate::tests::generate_fasta(42, 5, 150)?;
+            let file = crate::tests::generate_fasta(42, 5, 150)?;
             let mut producer = Producer::with_blocksize(500, file)?;
 
             let mut comments = Vec::new();
@@ -263,5 +717,258 @@ Praesent porta sapien id tortor hendrerit, a hendrerit dolor commodo. Donec sed
 
             Ok(())
         }
+
+        #[test]
+        fn normalisation() -> error::Result<()> {
+            let file = crate::tests::write_in_tempfile(
+                b"\r\n>0\r\nACGT\r\n\n>1\nTTGCA\r\n>empty\n\n>2\nGG",
+            )?;
+
+            let mut records = Vec::new();
+            for block in Producer::with_blocksize(12, file.path())? {
+                let block = block?;
+                let mut reader = Reader::new(block);
+
+                while let Some(record) = reader.next_record()? {
+                    records.push((record.comment.to_vec(), record.sequence.to_vec()));
+                }
+            }
+
+            assert_eq!(
+                records,
+                vec![
+                    (b">0".to_vec(), b"ACGT".to_vec()),
+                    (b">1".to_vec(), b"TTGCA".to_vec()),
+                    (b">empty".to_vec(), b"".to_vec()),
+                    (b">2".to_vec(), b"GG".to_vec()),
+                ]
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn reader_mut() -> error::Result<()> {
+            let file = crate::tests::write_in_tempfile(b">0\nACgtN\n>1\nttRA\n")?;
+
+            let mut producer = Producer::new(file.path())?;
+            producer.set_map_options(block::MapOptions {
+                copy_on_write: true,
+                ..block::MapOptions::default()
+            });
+
+            let mut blocks = Vec::new();
+            for block in producer {
+                let mut reader = ReaderMut::new(block?);
+
+                while let Some(record) = reader.next_record()? {
+                    record.sequence.make_ascii_uppercase();
+                    for nuc in record.sequence.iter_mut() {
+                        if !b"ACGT".contains(nuc) {
+                            *nuc = b'N';
+                        }
+                    }
+                }
+
+                blocks.push(reader.into_block());
+            }
+
+            let output = tempfile::NamedTempFile::new()?;
+            block::write_blocks(blocks, output.path())?;
+
+            assert_eq!(std::fs::read(output.path())?, b">0\nACGTN\n>1\nTTNA\n");
+            assert_eq!(std::fs::read(file.path())?, b">0\nACgtN\n>1\nttRA\n");
+
+            Ok(())
+        }
+
+        #[test]
+        fn lenient() -> error::Result<()> {
+            let data = b">0\nACGT\nACGT\n>1\nGG\n>2\n>3\nTT\n";
+            let block =
+                block::Block::from_buffer(0..data.len(), std::sync::Arc::new(data.to_vec()));
+
+            let mut reader = Reader::new(block.clone());
+            reader.set_parse_mode(block::ParseMode::Lenient);
+
+            let mut records = Vec::new();
+            while let Some(record) = reader.next_record()? {
+                records.push((record.comment.to_vec(), record.sequence.to_vec()));
+            }
+
+            assert_eq!(
+                records,
+                vec![
+                    (b">0".to_vec(), b"ACGT".to_vec()),
+                    (b">1".to_vec(), b"GG".to_vec()),
+                    (b">3".to_vec(), b"TT".to_vec()),
+                ]
+            );
+            assert_eq!(
+                reader.skipped(),
+                block::Skipped {
+                    records: 2,
+                    bytes: 8
+                }
+            );
+
+            let mut reader = Reader::new(block);
+            let mut count = 0;
+            while let Some(_record) = reader.next_record()? {
+                count += 1;
+            }
+            assert_eq!(count, 4);
+
+            Ok(())
+        }
+
+        #[test]
+        fn owned_record() -> error::Result<()> {
+            let file = crate::tests::write_in_tempfile(b">0\nACGT\n>1\nTTGCA\n")?;
+
+            let mut reader = Reader::new(Producer::new(file.path())?.next_block()?.unwrap());
+            let first = reader.next_record()?.unwrap().to_owned();
+
+            let mut second = OwnedRecord::default();
+            assert!(reader.next_record_into(&mut second)?);
+            assert!(!reader.next_record_into(&mut second)?);
+
+            assert_eq!(first.comment, b">0");
+            assert_eq!(first.as_ref().sequence, b"ACGT");
+            assert_eq!(second.sequence, b"TTGCA");
+            assert_eq!(second.file_offset(), 8);
+
+            Ok(())
+        }
+    }
+
+    mod chunk {
+        use super::*;
+
+        #[test]
+        fn split() -> error::Result<()> {
+            let file = crate::tests::write_in_tempfile(
+                b">0\nACGTACGTACGTACGTACGTACGTA\n>1\nTTGCA\n\n>2\r\nGGGGGGGGGGC\r\n",
+            )?;
+
+            let chunks = ChunkProducer::new(10, 2, file.path())?
+                .map(|chunk| {
+                    chunk.map(|chunk| {
+                        (
+                            chunk.record_id,
+                            chunk.offset_in_record,
+                            chunk.bases.data().to_vec(),
+                            chunk.bases.offset(),
+                        )
+                    })
+                })
+                .collect::<error::Result<Vec<_>>>()?;
+
+            assert_eq!(
+                chunks,
+                vec![
+                    (0, 0, b"ACGTACGTACGT".to_vec(), 3),
+                    (0, 10, b"GTACGTACGTAC".to_vec(), 13),
+                    (0, 20, b"ACGTA".to_vec(), 23),
+                    (1, 0, b"TTGCA".to_vec(), 32),
+                    (2, 0, b"GGGGGGGGGGC".to_vec(), 43),
+                ]
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn kmer() -> error::Result<()> {
+            let file = crate::tests::generate_fasta(42, 10, 1_000)?;
+            let k = 21;
+
+            let kmers = ChunkProducer::new(64, k - 1, file.path())?
+                .map(|chunk| chunk.map(|chunk| chunk.bases.data().windows(k as usize).count()))
+                .sum::<error::Result<usize>>()?;
+
+            assert_eq!(kmers, 10 * (1_000 - k as usize + 1));
+
+            Ok(())
+        }
+
+        #[test]
+        fn wrapped() -> error::Result<()> {
+            let file =
+                crate::tests::write_in_tempfile(b">0\nACGTA\nCGTAC\nGT\n>1\r\nTT\r\nGCA\r\n")?;
+
+            let chunks = ChunkProducer::new(4, 2, file.path())?
+                .map(|chunk| {
+                    chunk.map(|chunk| {
+                        (
+                            chunk.record_id,
+                            chunk.offset_in_record,
+                            chunk.bases.data().to_vec(),
+                            chunk.bases.offset(),
+                        )
+                    })
+                })
+                .collect::<error::Result<Vec<_>>>()?;
+
+            assert_eq!(
+                chunks,
+                vec![
+                    (0, 0, b"ACGTAC".to_vec(), 3),
+                    (0, 4, b"ACGTAC".to_vec(), 7),
+                    (0, 8, b"ACGT".to_vec(), 12),
+                    (1, 0, b"TTGCA".to_vec(), 22),
+                ]
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn blocks() -> error::Result<()> {
+            let file = crate::tests::generate_fasta(42, 10, 1_000)?;
+            let k = 21;
+
+            let producer = Producer::with_blocksize(1_500, file.path())?;
+            let chunks = ChunkProducer::from_producer(64, k - 1, producer)
+                .collect::<error::Result<Vec<SequenceChunk>>>()?;
+
+            let mut blocks = chunks
+                .iter()
+                .map(|chunk| chunk.bases.index())
+                .collect::<Vec<u64>>();
+            blocks.dedup();
+            assert!(blocks.len() > 1);
+
+            let mut records = chunks
+                .iter()
+                .map(|chunk| chunk.record_id)
+                .collect::<Vec<u64>>();
+            records.dedup();
+            assert_eq!(records, (0..10).collect::<Vec<u64>>());
+
+            assert_eq!(
+                chunks
+                    .iter()
+                    .map(|chunk| chunk.bases.data().windows(k as usize).count())
+                    .sum::<usize>(),
+                10 * (1_000 - k as usize + 1)
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn not_a_fasta() -> error::Result<()> {
+            let file = crate::tests::write_in_tempfile(b"ACGT\n>0\nACGT\n")?;
+
+            let mut producer = ChunkProducer::new(10, 0, file.path())?;
+            assert!(producer.next_chunk().is_err());
+            assert!(producer.next_chunk()?.is_none());
+
+            let producer = ChunkProducer::new(10, 0, file.path())?;
+            assert_eq!(producer.count(), 1);
+
+            Ok(())
+        }
     }
 }
@@ -3,14 +3,27 @@
 /* std use */
 
 /* crate use */
-use bstr::ByteSlice;
 
 /* project use */
 use crate::block;
+use crate::block::BlockProducer as _;
 use crate::error;
-use crate::impl_producer;
 use crate::impl_reader;
 
+/// Level of checks [Reader] done on each record
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Validation {
+    /// No check, any 4 lines are a record
+    #[default]
+    None,
+
+    /// Header begin by `@`, plus line begin by `+`, sequence and quality have same length
+    Basic,
+
+    /// Basic checks, plus line repeat header name if present and quality characters are between `!` and `~`
+    Full,
+}
+
 /// Strutt that store a fastq record
 pub struct Record<'a> {
     /// Fastq comment, without `>`
@@ -21,75 +34,394 @@ pub struct Record<'a> {
     pub plus: &'a [u8],
     /// Fastq quality
     pub quality: &'a [u8],
+
+    origin: block::Origin<'a>,
 }
 
-impl_producer!(Producer, |block: &[u8]| {
-    let mut end = block.len();
-
-    for _ in 0..5 {
-        end = block[..end]
-            .rfind_byte(b'\n')
-            .ok_or(error::Error::NoNewLineInBlock)?;
-
-        if end + 1 < block.len() && block[end + 1] == b'@' {
-            let prev = block[..end]
-                .rfind_byte(b'\n')
-                .ok_or(error::Error::NoNewLineInBlock)?;
-            if block[prev + 1] == b'+' {
-                let prevprev = block[..prev]
-                    .rfind_byte(b'\n')
-                    .ok_or(error::Error::NoNewLineInBlock)?;
-                if block[prevprev + 1] == b'+' {
-                    return Ok((end + 1) as u64);
-                } else {
-                    let prevprevprev = block[..prevprev]
-                        .rfind_byte(b'\n')
-                        .ok_or(error::Error::NoNewLineInBlock)?;
-                    if block[prevprevprev + 1] == b'@' {
-                        return Ok((prevprevprev + 1) as u64);
-                    } else {
-                        return Err(error::Error::NotAFastqFile);
+impl<'a> Record<'a> {
+    /// Get origin of record
+    pub fn origin(&self) -> block::Origin<'a> {
+        self.origin
+    }
+
+    /// Check record at a validation level, first violation is returned
+    pub fn validate(&self, validation: Validation) -> error::Result<()> {
+        if validation == Validation::None {
+            return Ok(());
+        }
+
+        if self.comment.first() != Some(&b'@') {
+            return Err(error::FastqError::HeaderPrefix.into());
+        }
+        if self.plus.first() != Some(&b'+') {
+            return Err(error::FastqError::PlusPrefix.into());
+        }
+        if self.sequence.len() != self.quality.len() {
+            return Err(error::FastqError::LengthMismatch {
+                sequence: self.sequence.len(),
+                quality: self.quality.len(),
+            }
+            .into());
+        }
+
+        if validation == Validation::Full {
+            if self.plus.len() > 1 && self.plus[1..] != self.comment[1..] {
+                return Err(error::FastqError::PlusName.into());
+            }
+
+            if let Some(position) = self.quality.iter().position(|c| !(b'!'..=b'~').contains(c)) {
+                return Err(error::FastqError::QualityRange {
+                    position,
+                    value: self.quality[position],
+                }
+                .into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get absolute offset of record in file
+    pub fn file_offset(&self) -> u64 {
+        self.origin.file_offset
+    }
+
+    /// Copy record in an owned record
+    pub fn to_owned(&self) -> OwnedRecord {
+        let mut owned = OwnedRecord::default();
+        self.copy_to(&mut owned);
+
+        owned
+    }
+
+    /// Copy record in an owned record, memory of owned record is reused
+    pub fn copy_to(&self, owned: &mut OwnedRecord) {
+        owned.comment.clear();
+        owned.comment.extend_from_slice(self.comment);
+        owned.sequence.clear();
+        owned.sequence.extend_from_slice(self.sequence);
+        owned.plus.clear();
+        owned.plus.extend_from_slice(self.plus);
+        owned.quality.clear();
+        owned.quality.extend_from_slice(self.quality);
+        owned.file_offset = self.origin.file_offset;
+    }
+}
+
+/// Strutt that store a fastq record that own its data
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OwnedRecord {
+    /// Fastq comment
+    pub comment: Vec<u8>,
+    /// Fastq sequence
+    pub sequence: Vec<u8>,
+    /// Fastq plus line
+    pub plus: Vec<u8>,
+    /// Fastq quality
+    pub quality: Vec<u8>,
+
+    file_offset: u64,
+}
+
+impl OwnedRecord {
+    /// Get a record that borrow data of owned record
+    pub fn as_ref(&self) -> Record<'_> {
+        Record {
+            comment: &self.comment,
+            sequence: &self.sequence,
+            plus: &self.plus,
+            quality: &self.quality,
+            origin: block::Origin {
+                file_offset: self.file_offset,
+                ..block::Origin::default()
+            },
+        }
+    }
+
+    /// Get absolute offset of record in file
+    pub fn file_offset(&self) -> u64 {
+        self.file_offset
+    }
+}
+
+/// Strutt that store a mutable fastq record, length of fields can't change to keep block layout
+pub struct RecordMut<'a> {
+    /// Fastq comment
+    pub comment: &'a mut [u8],
+    /// Fastq sequence
+    pub sequence: &'a mut [u8],
+    /// Fastq plus line
+    pub plus: &'a mut [u8],
+    /// Fastq quality
+    pub quality: &'a mut [u8],
+
+    file_offset: u64,
+}
+
+impl<'a> RecordMut<'a> {
+    /// Get absolute offset of record in file
+    pub fn file_offset(&self) -> u64 {
+        self.file_offset
+    }
+}
+
+/// Search the begin of the first fastq record that start at or after `from` in data
+///
+/// A record begin is confirmed if its header start with `@`, its plus line start with `+` and sequence and quality have same length, they could be empty. Position 0 of data is considered as a line start.
+///
+/// Return None if no record begin could be confirmed with available data, and [Error::AmbiguousRecordStart](error::Error::AmbiguousRecordStart) if a line inside the confirmed record could also be a complete record begin. A line inside the confirmed record that can't be completed with available data doesn't discard it.
+pub fn find_record_start(data: &[u8], from: usize) -> error::Result<Option<usize>> {
+    for pos in block::line_starts(data, from).filter(|pos| data[*pos] == b'@') {
+        match check_record(data, pos) {
+            Check::Invalid => continue,
+            Check::Incomplete => return Ok(None),
+            Check::Valid(end) => {
+                for other in block::line_starts(data, pos + 1)
+                    .take_while(|other| *other < end)
+                    .filter(|other| data[*other] == b'@')
+                {
+                    match check_record(data, other) {
+                        Check::Invalid | Check::Incomplete => continue,
+                        Check::Valid(_) => return Err(error::Error::AmbiguousRecordStart),
                     }
                 }
-            } else {
-                return Ok((end + 1) as u64);
+
+                return Ok(Some(pos));
             }
         }
     }
 
-    Err(error::Error::NotAFastqFile)
-});
+    Ok(None)
+}
+
+/// Status of a potential record
+enum Check {
+    /// Record is valid, contains position of next record
+    Valid(usize),
+    /// Record isn't valid
+    Invalid,
+    /// Data end before record end
+    Incomplete,
+}
+
+/// Get position after empty lines (`\n` or `\r\n`) that begin at pos
+fn skip_empty_lines(data: &[u8], mut pos: usize) -> usize {
+    loop {
+        match &data[pos..] {
+            [b'\n', ..] => pos += 1,
+            [b'\r', b'\n', ..] => pos += 2,
+            _ => return pos,
+        }
+    }
+}
+
+/// Check if a valid fastq record begin at pos
+fn check_record(data: &[u8], pos: usize) -> Check {
+    let mut lines = [0..0, 0..0, 0..0, 0..0];
+    let mut begin = pos;
+
+    for (index, line) in lines.iter_mut().enumerate() {
+        let next = match memchr::memchr(b'\n', &data[begin..]) {
+            Some(next) => next,
+            None => return Check::Incomplete,
+        };
+
+        *line = begin..begin + next;
+        begin += next + 1;
+
+        let valid = match index {
+            0 => data.get(line.start) == Some(&b'@'),
+            2 => data.get(line.start) == Some(&b'+'),
+            _ => true,
+        };
+        if !valid {
+            return Check::Invalid;
+        }
+    }
+
+    if lines[1].len() == lines[3].len() {
+        Check::Valid(begin)
+    } else {
+        Check::Invalid
+    }
+}
+
+/// Fastq block producer
+pub struct Producer {
+    state: block::ProducerState,
+}
+
+impl block::BlockProducer for Producer {
+    fn from_state(state: block::ProducerState) -> Self {
+        Self { state }
+    }
+
+    fn state(&self) -> &block::ProducerState {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut block::ProducerState {
+        &mut self.state
+    }
+
+    fn correct_block_size(block: &[u8]) -> error::Result<u64> {
+        // block begin by a record, if it isn't valid data isn't fastq
+        let start = skip_empty_lines(block, 0);
+        match check_record(block, start) {
+            Check::Valid(_) => (),
+            Check::Invalid => return Err(error::Error::NotAFastqFile),
+            Check::Incomplete => return Err(error::Error::NoNewLineInBlock),
+        }
+
+        let mut window = 1024;
+
+        loop {
+            let from = block.len().saturating_sub(window);
+
+            match find_record_start(block, from)? {
+                Some(mut pos) => loop {
+                    match check_record(block, pos) {
+                        Check::Valid(next) => pos = skip_empty_lines(block, next),
+                        Check::Incomplete if pos == start => {
+                            return Err(error::Error::NoNewLineInBlock)
+                        }
+                        Check::Incomplete => return Ok(pos as u64),
+                        Check::Invalid => return Err(error::Error::NotAFastqFile),
+                    }
+                },
+                None if from == 0 => return Err(error::Error::NoNewLineInBlock),
+                None => window *= 2,
+            }
+        }
+    }
+
+    fn find_record_start(data: &[u8], from: usize) -> error::Result<Option<usize>> {
+        find_record_start(data, from)
+    }
+}
+
+impl Iterator for Producer {
+    type Item = error::Result<block::Block>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_block().transpose()
+    }
+}
 
 impl_reader!(
     Reader,
     'a,
-    Record,
-    |block: &'a block::Block, offset: &mut usize| {
+    Record<'a>,
+    Validation,
+    |block: &'a block::Block, offset: &mut usize, mode: block::ParseMode, validation: &Validation| {
+        Self::skip_empty_lines(block, offset);
+
         if *offset == block.len() {
             Ok(None)
         } else {
-            let comment = &block.data()[Self::get_line(block, offset)?];
-            *offset += comment.len() + 1;
-
-            let sequence = &block.data()[Self::get_line(block, offset)?];
-            *offset += sequence.len() + 1;
+            let origin = block.origin_at(*offset);
 
-            let plus = &block.data()[Self::get_line(block, offset)?];
-            *offset += plus.len() + 1;
+            let comment = Self::next_line(block, offset)?;
+            let sequence = Self::next_line(block, offset)?;
+            let plus = Self::next_line(block, offset)?;
+            let quality = Self::next_line(block, offset)?;
 
-            let quality = &block.data()[Self::get_line(block, offset)?];
-            *offset += quality.len() + 1;
-
-            Ok(Some(Record {
+            let record = Record {
                 comment,
                 sequence,
                 plus,
                 quality,
-            }))
+                origin,
+            };
+
+            if mode == block::ParseMode::Lenient {
+                record.validate((*validation).max(Validation::Basic))?;
+            } else {
+                record.validate(*validation)?;
+            }
+
+            Ok(Some(record))
         }
+    },
+    Self::skip_empty_lines,
+    |data: &[u8], from: usize| {
+        find_record_start(data, from)
+            .ok()
+            .flatten()
+            .unwrap_or(data.len())
     }
 );
 
+impl Reader {
+    /// Get validation level of records
+    pub fn validation(&self) -> Validation {
+        self.options
+    }
+
+    /// Set validation level of records, in [ParseMode::Lenient](block::ParseMode::Lenient) records are validated at least at [Validation::Basic] level
+    pub fn set_validation(&mut self, validation: Validation) {
+        self.options = validation;
+    }
+
+    /// Copy next available record in owned, memory of owned record is reused, return false if block is consumed
+    pub fn next_record_into(&mut self, owned: &mut OwnedRecord) -> error::Result<bool> {
+        match self.next_record()? {
+            Some(record) => {
+                record.copy_to(owned);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+/// Reader that give mutable records
+///
+/// Block is edited in place if it's the only owner of a private mapping (see [MapOptions::copy_on_write](block::MapOptions::copy_on_write)), in other case block data is copied.
+pub struct ReaderMut {
+    offset: usize,
+    block: block::Block,
+}
+
+impl ReaderMut {
+    /// Create a new ReaderMut
+    pub fn new(block: block::Block) -> Self {
+        Self { offset: 0, block }
+    }
+
+    /// Get the next available record
+    pub fn next_record(&mut self) -> error::Result<Option<RecordMut<'_>>> {
+        Reader::skip_empty_lines(&self.block, &mut self.offset);
+
+        if self.offset == self.block.len() {
+            return Ok(None);
+        }
+
+        let file_offset = self.block.origin_at(self.offset).file_offset;
+
+        let comment = Reader::next_line_range(&self.block, &mut self.offset)?;
+        let sequence = Reader::next_line_range(&self.block, &mut self.offset)?;
+        let plus = Reader::next_line_range(&self.block, &mut self.offset)?;
+        let quality = Reader::next_line_range(&self.block, &mut self.offset)?;
+
+        let [comment, sequence, plus, quality] =
+            block::split_ranges_mut(self.block.data_mut(), [comment, sequence, plus, quality]);
+
+        Ok(Some(RecordMut {
+            comment,
+            sequence,
+            plus,
+            quality,
+            file_offset,
+        }))
+    }
+
+    /// Get edited block
+    pub fn into_block(self) -> block::Block {
+        self.block
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -157,7 +489,7 @@ iCW?:KL~15\\E|MNRKY)S$?~~Ub}d)dY2LX:e@b^'<<$$e56W0fdV,<Y>Yd(J<5p6xt)z+OxuPXv?/_y
 
         #[test]
         fn with_blocksize_buffer_larger_file() -> error::Result<()> {
-            let file = crate::tests::generate_fastq(42, 100, 150)?;
+            let file = crate::tests::generate_fastq(42, 2, 150)?;
             let mut tmp = Producer::with_blocksize(8092, file).unwrap();
 
             let block = tmp.next_block().unwrap().unwrap();
@@ -169,7 +501,7 @@ iCW?:KL~15\\E|MNRKY)S$?~~Ub}d)dY2LX:e@b^'<<$$e56W0fdV,<Y>Yd(J<5p6xt)z+OxuPXv?/_y
 
         #[test]
         fn get_all_block() -> error::Result<()> {
-            let file = crate::tests::generate_fastq(42, 100, 150)?;
+            let file = crate::tests::generate_fastq(42, 1_000, 150)?;
             let mut tmp = Producer::new(file).unwrap();
 
             let mut block_length = Vec::new();
@@ -184,7 +516,7 @@ iCW?:KL~15\\E|MNRKY)S$?~~Ub}d)dY2LX:e@b^'<<$$e56W0fdV,<Y>Yd(J<5p6xt)z+OxuPXv?/_y
 
         #[test]
         fn check_block() -> error::Result<()> {
-            let file = crate::tests::generate_fastq(42, 100, 150)?;
+            let file = crate::tests::generate_fastq(42, 5, 150)?;
 
             let mut tmp = Producer::with_blocksize(800, file)?;
 
@@ -240,9 +572,135 @@ myS=C|jEWnl,aC\\7!jv9[!vh/PAK}_H&<.o]qf|y@4L:?ssLg3N!v7/N5RyPHn=5%Fyh(4-Z:<6wf]^
             Ok(())
         }
 
+        #[test]
+        fn record_larger_than_blocksize() -> error::Result<()> {
+            let file = crate::tests::generate_fastq(42, 10, 1_000)?;
+
+            let mut tmp = Producer::with_blocksize(100, file.path())?;
+
+            let mut block_length = Vec::new();
+            while let Some(block) = tmp.next_block()? {
+                block_length.push(block.len());
+            }
+
+            assert_eq!(block_length, vec![2008; 10]);
+
+            let mut tmp = Producer::with_blocksize(100, file.path())?;
+            tmp.set_max_blocksize(1_600);
+
+            assert!(tmp.next_block().is_err());
+
+            Ok(())
+        }
+
+        #[test]
+        fn auto() -> error::Result<()> {
+            let file = crate::tests::generate_fastq(42, 2_000, 150)?;
+
+            let mut producer = Producer::auto_with_records(100, file.path())?;
+            let estimate = producer.blocksize_estimate().unwrap();
+
+            assert_eq!(estimate.records, 1_999);
+            assert_eq!(estimate.lines_per_record, 4.0);
+            assert!(estimate.record_length > 310.0 && estimate.record_length < 315.0);
+            assert_eq!(estimate.max_record_length, 314);
+            assert_eq!(estimate.blocksize, 32768);
+            assert_eq!(producer.blocksize(), 32768);
+
+            let block = producer.next_block()?.unwrap();
+            let mut reader = Reader::new(block);
+            let mut records = 0;
+            while reader.next_record()?.is_some() {
+                records += 1;
+            }
+            assert!((100..110).contains(&records));
+
+            Ok(())
+        }
+
+        #[test]
+        fn with_range() -> error::Result<()> {
+            let file = crate::tests::generate_fastq(42, 1_000, 150)?;
+            let length = Producer::filesize(&file.path())?;
+            let limits = [0, 1, 308, 5_000, 77_777, 150_000, length - 1, length];
+
+            let mut comments = Vec::new();
+            for range in limits.windows(2) {
+                let mut producer =
+                    Producer::with_blocksize_range(1_000, range[0], range[1], file.path())?;
+
+                while let Some(block) = producer.next_block()? {
+                    let mut reader = Reader::new(block);
+
+                    while let Some(record) = reader.next_record()? {
+                        comments.push(String::from_utf8(record.comment.to_vec()).unwrap());
+                    }
+                }
+            }
+
+            assert_eq!(
+                comments,
+                (0..1_000)
+                    .map(|i| format!("@{}", i))
+                    .collect::<Vec<String>>()
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn find_record_start() -> error::Result<()> {
+            let data = b"@1\nAA\n+1\n@!\n@2\nTT\n+2\n!!\n";
+
+            assert_eq!(Producer::find_record_start(data, 0)?, Some(0));
+            assert_eq!(Producer::find_record_start(data, 1)?, Some(12));
+            assert_eq!(Producer::find_record_start(data, 13)?, None);
+
+            let data = b"@1\nAAA\n+\n!!\n@2\nTT\n+\n!!\n";
+            assert_eq!(super::find_record_start(data, 0)?, Some(12));
+
+            let data = b"@a\nAC\n+\n@B\n+C\n+\n@D\n";
+            assert!(matches!(
+                super::find_record_start(data, 0),
+                Err(error::Error::AmbiguousRecordStart)
+            ));
+
+            Ok(())
+        }
+
+        #[test]
+        fn quality_begin_by_at() -> error::Result<()> {
+            let data = b"@a\nACGT\n+\nIIII\n@b\nACGT\n+\n@III\n@c\nACGT\n+\n@III\n";
+            assert_eq!(super::find_record_start(data, 17)?, Some(30));
+
+            let file = crate::tests::write_in_tempfile(data)?;
+            let limits = [0, 17, 40, data.len() as u64];
+
+            let mut comments = Vec::new();
+            for range in limits.windows(2) {
+                let mut producer =
+                    Producer::with_blocksize_range(1_000, range[0], range[1], file.path())?;
+
+                while let Some(block) = producer.next_block()? {
+                    let mut reader = Reader::new(block);
+
+                    while let Some(record) = reader.next_record()? {
+                        comments.push(record.comment.to_vec());
+                    }
+                }
+            }
+
+            assert_eq!(
+                comments,
+                vec![b"@a".to_vec(), b"@b".to_vec(), b"@c".to_vec()]
+            );
+
+            Ok(())
+        }
+
         #[test]
         fn not_a_fastq() -> error::Result<()> {
-            let mut file = crate::tests::write_in_tempfile(
+            let file = crate::tests::write_in_tempfile(
                 b"@0
 TTAGATTATAGTACGG
 ATTATAT
@@ -255,13 +713,13 @@ GTCCCTCAATCCG
 ",
             )?;
 
-            let mut producer = Producer::with_blocksize(82, file)?;
+            let mut producer = Producer::with_blocksize(82, file.path())?;
 
             assert!(producer.next_block().is_err());
 
             {
                 let mut rewrite = file.reopen().unwrap();
-                rewrite.write(
+                rewrite.write_all(
                     b"+FAILLED FILE
 +3
 +TTGGGCATGAGGTTCA
@@ -272,7 +730,7 @@ GTCCCTCAATCCG
                 )?;
             }
 
-            let mut producer = Producer::with_blocksize(82, file)?;
+            let mut producer = Producer::with_blocksize(82, file.path())?;
 
             assert!(producer.next_block().is_err());
 
@@ -289,7 +747,7 @@ GTCCCTCAATCCG
 
         #[test]
         fn iterate_over_seq() -> error::Result<()> {
-            let mut file = crate::tests::generate_fasta(42, 5, 150)?;
+            let file = crate::tests::generate_fastq(42, 5, 150)?;
             let mut producer = Producer::with_blocksize(500, file)?;
 
             let mut comments = Vec::new();
@@ -348,5 +806,544 @@ GTCCCTCAATCCG
 
             Ok(())
         }
+
+        #[test]
+        fn file_offset() -> error::Result<()> {
+            let file = crate::tests::generate_fastq(42, 5, 150)?;
+            let mut producer = Producer::with_blocksize(700, file)?;
+
+            let mut offsets = Vec::new();
+            while let Some(block) = producer.next_block()? {
+                let index = block.index();
+                let mut reader = Reader::new(block);
+
+                while let Some(record) = reader.next_record()? {
+                    offsets.push((index, record.file_offset()));
+                }
+            }
+
+            assert_eq!(
+                offsets,
+                vec![(0, 0), (0, 308), (1, 616), (1, 924), (2, 1232)]
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn buffered() -> error::Result<()> {
+            let file = crate::tests::generate_fastq(42, 1_000, 150)?;
+            let data = std::fs::read(file.path())?;
+
+            let mut producer = block::buffered::Producer::<Producer, _>::new(&data[..]);
+
+            let mut comments = Vec::new();
+            while let Some(block) = producer.next_block()? {
+                let mut reader = Reader::new(block);
+
+                while let Some(record) = reader.next_record()? {
+                    comments.push(String::from_utf8(record.comment.to_vec()).unwrap());
+                }
+            }
+
+            assert_eq!(
+                comments,
+                (0..1_000)
+                    .map(|i| format!("@{}", i))
+                    .collect::<Vec<String>>()
+            );
+
+            Ok(())
+        }
+
+        #[cfg(feature = "gzip")]
+        #[test]
+        fn gzip() -> error::Result<()> {
+            use std::io::Write as _;
+
+            let file = crate::tests::generate_fastq(42, 1_000, 150)?;
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(&std::fs::read(file.path())?)?;
+            let compressed = crate::tests::write_in_tempfile(&encoder.finish()?)?;
+
+            let mut comments = Vec::new();
+            for block in Producer::with_blocksize(8192, compressed.path())? {
+                let mut reader = Reader::new(block?);
+
+                while let Some(record) = reader.next_record()? {
+                    comments.push(String::from_utf8(record.comment.to_vec()).unwrap());
+                }
+            }
+
+            assert_eq!(
+                comments,
+                (0..1_000)
+                    .map(|i| format!("@{}", i))
+                    .collect::<Vec<String>>()
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn memory() -> error::Result<()> {
+            let file = crate::tests::generate_fastq(42, 1_000, 150)?;
+            let data = std::fs::read(file.path())?;
+
+            let mut mapped = Vec::new();
+            for block in Producer::with_blocksize(8192, file.path())? {
+                let block = block?;
+                let mut reader = Reader::new(block);
+
+                while let Some(record) = reader.next_record()? {
+                    mapped.push((record.file_offset(), record.sequence.to_vec()));
+                }
+            }
+
+            let mut memory = Vec::new();
+            for block in block::memory::Producer::<Producer>::with_blocksize(8192, data) {
+                let mut reader = Reader::new(block?);
+
+                while let Some(record) = reader.next_record()? {
+                    memory.push((record.file_offset(), record.sequence.to_vec()));
+                }
+            }
+
+            assert_eq!(mapped.len(), 1_000);
+            assert_eq!(mapped, memory);
+
+            Ok(())
+        }
+
+        #[test]
+        fn empty_lines_between_blocks() -> error::Result<()> {
+            use std::io::Write as _;
+
+            let mut data = Vec::new();
+            for index in 0..2_000 {
+                write!(data, "\n@{}\nACGTACGT\n+\n!!!!!!!!\n", index)?;
+                if index % 3 == 0 {
+                    data.extend_from_slice(b"\r\n");
+                }
+            }
+            let file = crate::tests::write_in_tempfile(&data)?;
+
+            let mut producer = Producer::with_blocksize(4_096, file.path())?;
+            producer.set_max_blocksize(65_536);
+
+            let mut blocks = 0;
+            let mut comments = Vec::new();
+            for block in producer {
+                let block = block?;
+                assert!(block.len() <= 4_096);
+                blocks += 1;
+
+                let mut reader = Reader::new(block);
+                while let Some(record) = reader.next_record()? {
+                    comments.push(record.comment.to_vec());
+                }
+            }
+
+            assert!(blocks > 1);
+            assert_eq!(
+                comments,
+                (0..2_000)
+                    .map(|index| format!("@{}", index).into_bytes())
+                    .collect::<Vec<Vec<u8>>>()
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn zero_length_reads() -> error::Result<()> {
+            use std::io::Write as _;
+
+            let mut data = Vec::new();
+            for index in 0..200 {
+                if index % 2 == 0 {
+                    write!(data, "@{}\n\n+\n\n", index)?;
+                } else {
+                    write!(data, "@{}\nACGT\n+\nIIII\n", index)?;
+                }
+            }
+            let file = crate::tests::write_in_tempfile(&data)?;
+
+            for blocksize in [100, 4_096] {
+                let mut comments = Vec::new();
+                for block in Producer::with_blocksize(blocksize, file.path())? {
+                    let mut reader = Reader::new(block?);
+                    while let Some(record) = reader.next_record()? {
+                        assert_eq!(record.sequence.len(), record.quality.len());
+                        comments.push(record.comment.to_vec());
+                    }
+                }
+
+                assert_eq!(
+                    comments,
+                    (0..200)
+                        .map(|index| format!("@{}", index).into_bytes())
+                        .collect::<Vec<Vec<u8>>>()
+                );
+            }
+
+            Ok(())
+        }
+
+        #[test]
+        fn normalisation() -> error::Result<()> {
+            let file = crate::tests::write_in_tempfile(
+                b"@0\r\nACGT\r\n+\r\n!!!!\r\n\n\r\n@1\nTTGCA\n+\n!!!!!\n\n@2\nGG\n+\n!!",
+            )?;
+
+            let mut records = Vec::new();
+            for block in Producer::new(file.path())? {
+                let block = block?;
+                let mut reader = Reader::new(block);
+
+                while let Some(record) = reader.next_record()? {
+                    records.push((
+                        record.comment.to_vec(),
+                        record.sequence.to_vec(),
+                        record.plus.to_vec(),
+                        record.quality.to_vec(),
+                    ));
+                }
+            }
+
+            assert_eq!(
+                records,
+                vec![
+                    (
+                        b"@0".to_vec(),
+                        b"ACGT".to_vec(),
+                        b"+".to_vec(),
+                        b"!!!!".to_vec()
+                    ),
+                    (
+                        b"@1".to_vec(),
+                        b"TTGCA".to_vec(),
+                        b"+".to_vec(),
+                        b"!!!!!".to_vec()
+                    ),
+                    (
+                        b"@2".to_vec(),
+                        b"GG".to_vec(),
+                        b"+".to_vec(),
+                        b"!!".to_vec()
+                    ),
+                ]
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn reader_mut() -> error::Result<()> {
+            let file = crate::tests::write_in_tempfile(b"@0\nACGT\n+\nI!I#\n@1\nTTGA\n+\n!III\n")?;
+
+            let mut blocks = Vec::new();
+            for block in Producer::new(file.path())? {
+                let mut reader = ReaderMut::new(block?);
+
+                while let Some(record) = reader.next_record()? {
+                    for (nuc, qual) in record.sequence.iter_mut().zip(record.quality.iter()) {
+                        if *qual < b'#' {
+                            *nuc = b'N';
+                        }
+                    }
+                }
+
+                blocks.push(reader.into_block());
+            }
+
+            let output = tempfile::NamedTempFile::new()?;
+            block::write_blocks(blocks, output.path())?;
+
+            assert_eq!(
+                std::fs::read(output.path())?,
+                b"@0\nANGT\n+\nI!I#\n@1\nNTGA\n+\n!III\n"
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn reverse() -> error::Result<()> {
+            let file = crate::tests::generate_fastq(42, 1_000, 150)?;
+
+            let mut producer =
+                block::reverse::Producer::<Producer>::with_blocksize(1000, file.path())?;
+            let mut reader = Reader::new(producer.next_block()?.unwrap());
+
+            let mut comments = Vec::new();
+            while let Some(record) = reader.next_record()? {
+                comments.push(String::from_utf8(record.comment.to_vec()).unwrap());
+            }
+
+            assert_eq!(comments, vec!["@997", "@998", "@999"]);
+
+            Ok(())
+        }
+
+        #[test]
+        fn sample() -> error::Result<()> {
+            let file = crate::tests::generate_fastq(42, 1_000, 150)?;
+
+            let mut records = 0;
+            for block in
+                block::sample::Producer::<Producer>::with_blocksize(2000, 5, 42, file.path())?
+            {
+                let mut reader = Reader::new(block?);
+
+                while let Some(record) = reader.next_record()? {
+                    assert_eq!(record.comment[0], b'@');
+                    assert_eq!(record.sequence.len(), record.quality.len());
+                    records += 1;
+                }
+            }
+
+            assert!(records > 0 && records < 1_000);
+
+            Ok(())
+        }
+
+        #[test]
+        fn record_reader() -> error::Result<()> {
+            fn count<R>(block: block::Block) -> error::Result<usize>
+            where
+                R: block::RecordReader,
+            {
+                let mut reader = R::from_block(block);
+
+                let mut records = 0;
+                while reader.next_record()?.is_some() {
+                    records += 1;
+                }
+
+                Ok(records)
+            }
+
+            let file = crate::tests::generate_fastq(42, 1_000, 150)?;
+
+            let mut records = 0;
+            for block in Producer::with_blocksize(8192, file.path())? {
+                records += count::<Reader>(block?)?;
+            }
+
+            assert_eq!(records, 1_000);
+
+            Ok(())
+        }
+
+        #[test]
+        fn lenient() -> error::Result<()> {
+            let data = b"@0\nACGT\n+\n!!!!\n@1\nACG\n+\n!!!!\n@2\nTT\n+\n!!\nbroken\n@3\nGG\n+\n!!\n@4\nA\n";
+            let block =
+                block::Block::from_buffer(0..data.len(), std::sync::Arc::new(data.to_vec()));
+
+            let mut reader = Reader::new(block.clone());
+            let mut comments = Vec::new();
+            let error = loop {
+                match reader.next_record() {
+                    Ok(Some(record)) => comments.push(record.comment.to_vec()),
+                    Ok(None) => break None,
+                    Err(e) => break Some(e),
+                }
+            };
+
+            assert_eq!(
+                comments,
+                vec![
+                    b"@0".to_vec(),
+                    b"@1".to_vec(),
+                    b"@2".to_vec(),
+                    b"broken".to_vec()
+                ]
+            );
+            assert!(matches!(
+                error.as_ref().map(error::Error::root),
+                Some(error::Error::PartialRecord)
+            ));
+
+            let mut reader = Reader::new(block);
+            reader.set_parse_mode(block::ParseMode::Lenient);
+
+            let mut comments = Vec::new();
+            while let Some(record) = reader.next_record()? {
+                comments.push(record.comment.to_vec());
+            }
+
+            assert_eq!(
+                comments,
+                vec![b"@0".to_vec(), b"@2".to_vec(), b"@3".to_vec()]
+            );
+            assert_eq!(
+                reader.skipped(),
+                block::Skipped {
+                    records: 3,
+                    bytes: 26
+                }
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn validation() -> error::Result<()> {
+            let first_error = |data: &[u8], validation: Validation| {
+                let block =
+                    block::Block::from_buffer(0..data.len(), std::sync::Arc::new(data.to_vec()));
+                let mut reader = Reader::new(block);
+                reader.set_validation(validation);
+
+                loop {
+                    match reader.next_record() {
+                        Ok(Some(_)) => (),
+                        Ok(None) => return None,
+                        Err(e) => return Some(e),
+                    }
+                }
+            };
+            assert!(first_error(b"@0\nACGT\n+\n!!!!\n@1\nA\n+1\n~\n", Validation::Full).is_none());
+            assert!(first_error(b"0\nACGT\n-\n!!\n", Validation::None).is_none());
+
+            assert!(matches!(
+                first_error(b"@0\nACGT\n+\n!!!!\n0\nACGT\n+\n!!!!\n", Validation::Basic)
+                    .as_ref()
+                    .map(error::Error::root),
+                Some(error::Error::FastqError(error::FastqError::HeaderPrefix))
+            ));
+            assert!(matches!(
+                first_error(b"@0\nACGT\n-\n!!!!\n", Validation::Basic)
+                    .as_ref()
+                    .map(error::Error::root),
+                Some(error::Error::FastqError(error::FastqError::PlusPrefix))
+            ));
+            assert!(matches!(
+                first_error(b"@0\nACGT\n+\n!!!\n", Validation::Basic)
+                    .as_ref()
+                    .map(error::Error::root),
+                Some(error::Error::FastqError(
+                    error::FastqError::LengthMismatch {
+                        sequence: 4,
+                        quality: 3
+                    }
+                ))
+            ));
+
+            assert!(first_error(b"@0\nACGT\n+1\n!! !\n", Validation::Basic).is_none());
+            assert!(matches!(
+                first_error(b"@0\nACGT\n+1\n!!!!\n", Validation::Full)
+                    .as_ref()
+                    .map(error::Error::root),
+                Some(error::Error::FastqError(error::FastqError::PlusName))
+            ));
+            assert!(matches!(
+                first_error(b"@0\nACGT\n+\n!! !\n", Validation::Full)
+                    .as_ref()
+                    .map(error::Error::root),
+                Some(error::Error::FastqError(error::FastqError::QualityRange {
+                    position: 2,
+                    value: b' '
+                }))
+            ));
+            assert_eq!(
+                first_error(b"@0\nACGT\n+\n!!!\n", Validation::Full).map(|e| e.root().to_string()),
+                Some("Fastq sequence length 4 and quality length 3 are different".to_string())
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn error_location() -> error::Result<()> {
+            let data = b"@0\nACGT\n+\n!!!!\n\n@1\nAC\n";
+            let file = crate::tests::write_in_tempfile(data)?;
+
+            let mut reader = Reader::new(Producer::new(file.path())?.next_block()?.unwrap());
+            assert!(reader.next_record()?.is_some());
+
+            match reader.next_record() {
+                Err(
+                    ref e @ error::Error::Located {
+                        ref path,
+                        offset,
+                        line,
+                        ref excerpt,
+                        ..
+                    },
+                ) => {
+                    assert!(matches!(e.root(), error::Error::PartialRecord));
+                    assert_eq!(path.as_deref(), Some(file.path()));
+                    assert_eq!(offset, 16);
+                    assert_eq!(line, 6);
+                    assert_eq!(excerpt, "@1\\nAC\\n");
+                    assert!(e
+                        .to_string()
+                        .contains("at byte 16, line 6 of block 0 at byte 0"));
+                }
+                _ => panic!("expect a located error"),
+            }
+
+            let data = b"@0\nA\n+\n!\n@1\nACGTACGTACGT\n+\n!!!!!!!!!!!!\n";
+            let mut producer = block::memory::Producer::<Producer>::with_blocksize(12, &data[..]);
+            producer.set_max_blocksize(12);
+            assert!(producer.next_block()?.is_some());
+
+            match producer.next_block() {
+                Err(error::Error::Located {
+                    path,
+                    offset,
+                    block,
+                    block_offset,
+                    line,
+                    ..
+                }) => {
+                    assert_eq!(path, None);
+                    assert_eq!(offset, 9);
+                    assert_eq!(block, 1);
+                    assert_eq!(block_offset, 9);
+                    assert_eq!(line, 1);
+                }
+                _ => panic!("expect a located error"),
+            }
+
+            Ok(())
+        }
+
+        #[test]
+        fn owned_record() -> error::Result<()> {
+            let file = crate::tests::generate_fastq(42, 100, 150)?;
+
+            let mut records = Vec::new();
+            for block in Producer::with_blocksize(8192, file.path())? {
+                let mut reader = Reader::new(block?);
+
+                while let Some(record) = reader.next_record()? {
+                    records.push(record.to_owned());
+                }
+            }
+
+            let mut owned = OwnedRecord::default();
+            let mut index = 0;
+            for block in Producer::with_blocksize(8192, file.path())? {
+                let mut reader = Reader::new(block?);
+
+                while reader.next_record_into(&mut owned)? {
+                    assert_eq!(owned, records[index]);
+                    assert_eq!(owned.as_ref().sequence, records[index].sequence);
+                    assert_eq!(owned.as_ref().file_offset(), owned.file_offset());
+                    index += 1;
+                }
+            }
+
+            assert_eq!(index, 100);
+            assert_eq!(records[1].comment, b"@1");
+            assert_eq!(records[1].file_offset(), 308);
+
+            Ok(())
+        }
     }
 }
@@ -24,6 +24,12 @@ pub mod parser;
 /// Define default blocksize
 pub const DEFAULT_BLOCKSIZE: u64 = 65536;
 
+/// Define default number of records a block should contains when blocksize is estimated
+pub const DEFAULT_RECORDS_PER_BLOCK: u64 = 1024;
+
+/// Define default maximal blocksize, a block grow up to this size to contain a complete record
+pub const DEFAULT_MAX_BLOCKSIZE: u64 = 1073741824;
+
 #[cfg(test)]
 mod tests {
     /* crate use */
@@ -60,6 +66,7 @@ mod tests {
         Ok(file)
     }
 
+    #[cfg(feature = "fasta")]
     pub fn generate_fasta(
         seed: u64,
         nb_seq: usize,
@@ -82,6 +89,7 @@ mod tests {
         Ok(file)
     }
 
+    #[cfg(any(feature = "fasta", feature = "fastq", feature = "vcf"))]
     pub fn write_in_tempfile(data: &[u8]) -> error::Result<tempfile::NamedTempFile> {
         let mut tempfile = tempfile::NamedTempFile::new()?;
 
@@ -1,6 +1,43 @@
 //! Module contains macro to generate some default parser.
+
+/* std use */
+
+/* crate use */
+
+/* project use */
+use crate::block;
+
 /* mod declaration */
 pub mod sequential;
 
 #[cfg(feature = "shared_state")]
 pub mod shared_state;
+
+/// Callback call by parsers after each block with progress of producer
+pub type ProgressCallback = Box<dyn Fn(block::Progress) + Send + Sync>;
+
+/// Report progress on `biommap::progress` log target and to callback if it's set
+pub fn report(progress: block::Progress, callback: Option<&ProgressCallback>) {
+    log::debug!(
+        target: "biommap::progress",
+        "{}/{} bytes ({:.2}%)",
+        progress.consumed,
+        progress.total,
+        progress.fraction() * 100.0
+    );
+
+    if let Some(callback) = callback {
+        callback(progress);
+    }
+}
+
+/// Log summary of data skipped by a parse in [ParseMode::Lenient](block::ParseMode::Lenient)
+pub fn summarize(skipped: block::Skipped) {
+    if skipped.records != 0 {
+        log::warn!(
+            "{} malformed records skipped ({} bytes)",
+            skipped.records,
+            skipped.bytes
+        );
+    }
+}
@@ -5,12 +5,36 @@
 /// Macro to generate a sequential parser
 #[macro_export(local_inner_macros)]
 macro_rules! impl_sequential {
-    ($name:ident, $producer:expr, $reader:expr,  $data_type:ty, $record:expr, $record_type:ty,) => {
-        pub struct $name {}
+    ($name:ident, $producer:expr, $reader:ty, $data_type:ty, $record:expr,) => {
+        pub struct $name {
+            progress: Option<$crate::parser::ProgressCallback>,
+            parse_mode: $crate::block::ParseMode,
+            skipped: $crate::block::Skipped,
+        }
 
+        #[allow(dead_code)]
         impl $name {
             pub fn new() -> Self {
-                Self {}
+                Self {
+                    progress: None,
+                    parse_mode: $crate::block::ParseMode::default(),
+                    skipped: $crate::block::Skipped::default(),
+                }
+            }
+
+            pub fn set_progress<F>(&mut self, callback: F)
+            where
+                F: Fn($crate::block::Progress) + Send + Sync + 'static,
+            {
+                self.progress = Some(Box::new(callback));
+            }
+
+            pub fn set_parse_mode(&mut self, mode: $crate::block::ParseMode) {
+                self.parse_mode = mode;
+            }
+
+            pub fn skipped(&self) -> $crate::block::Skipped {
+                self.skipped
             }
 
             pub fn parse<P>(&mut self, path: P, data: &mut $data_type) -> $crate::error::Result<()>
@@ -29,12 +53,77 @@ macro_rules! impl_sequential {
             where
                 P: AsRef<std::path::Path>,
             {
+                use $crate::block::BlockProducer as _;
+
                 let mut producer = $producer(blocksize, path)?;
+                producer.set_parse_mode(self.parse_mode);
+
+                let progress = self.progress.take();
+                let blocks = std::iter::from_fn(|| {
+                    let block = producer.next_block().transpose()?;
+                    $crate::parser::report(producer.progress(), progress.as_ref());
+                    Some(block)
+                });
+
+                let result = self.blocks(blocks, data);
+                self.progress = progress;
+
+                result
+            }
+
+            pub fn parse_files<I, P>(
+                &mut self,
+                paths: I,
+                data: &mut $data_type,
+            ) -> $crate::error::Result<()>
+            where
+                I: IntoIterator<Item = P>,
+                P: AsRef<std::path::Path>,
+            {
+                self.files_with_blocksize($crate::DEFAULT_BLOCKSIZE, paths, data)
+            }
+
+            pub fn files_with_blocksize<I, P>(
+                &mut self,
+                blocksize: u64,
+                paths: I,
+                data: &mut $data_type,
+            ) -> $crate::error::Result<()>
+            where
+                I: IntoIterator<Item = P>,
+                P: AsRef<std::path::Path>,
+            {
+                use $crate::block::BlockProducer as _;
+
+                let mut producer =
+                    $crate::block::MultiProducer::with_constructor(blocksize, paths, $producer);
+                producer.set_parse_mode(self.parse_mode);
+
+                let progress = self.progress.take();
+                let blocks = std::iter::from_fn(|| {
+                    let block = producer.next_block().transpose()?;
+                    $crate::parser::report(producer.progress(), progress.as_ref());
+                    Some(block)
+                });
+
+                let result = self.blocks(blocks, data);
+                self.progress = progress;
+
+                result
+            }
+
+            fn blocks<I>(&mut self, blocks: I, data: &mut $data_type) -> $crate::error::Result<()>
+            where
+                I: Iterator<Item = $crate::error::Result<$crate::block::Block>>,
+            {
+                self.skipped = $crate::block::Skipped::default();
 
-                while let Some(block) = producer.next_block()? {
-                    self.block(block, data)?
+                for block in blocks {
+                    self.block(block?, data)?
                 }
 
+                $crate::parser::summarize(self.skipped);
+
                 Ok(())
             }
 
@@ -43,16 +132,25 @@ macro_rules! impl_sequential {
                 block: $crate::block::Block,
                 data: &mut $data_type,
             ) -> $crate::error::Result<()> {
-                let mut reader = $reader(block);
+                use $crate::block::RecordReader as _;
+
+                let mut reader = <$reader>::from_block(block);
+                reader.set_parse_mode(self.parse_mode);
 
                 while let Some(record) = reader.next_record()? {
                     self.record(record, data);
                 }
 
+                self.skipped += reader.skipped();
+
                 Ok(())
             }
 
-            fn record(&self, record: $record_type, data: &mut $data_type) -> () {
+            fn record(
+                &self,
+                record: <$reader as $crate::block::RecordReader>::Record<'_>,
+                data: &mut $data_type,
+            ) -> () {
                 $record(record, data);
             }
         }
@@ -67,15 +165,14 @@ macro_rules! fasta_sequential {
         impl_sequential!(
             $name,
             $crate::fasta::Producer::with_blocksize,
-            $crate::fasta::Reader::new,
+            $crate::fasta::Reader,
             $data_type,
             $record,
-            $crate::fasta::Record,
         );
     };
 }
 
-/// Macro to generate a fasta sequential parser/// Macro to generate a fasta sequential parser
+/// Macro to generate a fastq sequential parser
 #[cfg(feature = "fastq")]
 #[macro_export(local_inner_macros)]
 macro_rules! fastq_sequential {
@@ -83,17 +180,90 @@ macro_rules! fastq_sequential {
         impl_sequential!(
             $name,
             $crate::fastq::Producer::with_blocksize,
-            $crate::fastq::Reader::new,
+            $crate::fastq::Reader,
             $data_type,
             $record,
-            $crate::fastq::Record,
         );
     };
 }
 
+#[cfg(feature = "fasta")]
+/// Macro to generate a fasta sequential parser over [SequenceChunk](crate::fasta::SequenceChunk) of sequences
+#[macro_export(local_inner_macros)]
+macro_rules! fasta_chunk_sequential {
+    ($name:ident, $data_type:ty, $chunk:expr) => {
+        pub struct $name {
+            chunk_length: u64,
+            overlap: u64,
+            progress: Option<$crate::parser::ProgressCallback>,
+        }
+
+        #[allow(dead_code)]
+        impl $name {
+            pub fn new(chunk_length: u64, overlap: u64) -> Self {
+                Self {
+                    chunk_length,
+                    overlap,
+                    progress: None,
+                }
+            }
+
+            pub fn set_progress<F>(&mut self, callback: F)
+            where
+                F: Fn($crate::block::Progress) + Send + Sync + 'static,
+            {
+                self.progress = Some(Box::new(callback));
+            }
+
+            pub fn parse<P>(&mut self, path: P, data: &mut $data_type) -> $crate::error::Result<()>
+            where
+                P: AsRef<std::path::Path>,
+            {
+                self.with_blocksize($crate::DEFAULT_BLOCKSIZE, path, data)
+            }
+
+            pub fn with_blocksize<P>(
+                &mut self,
+                blocksize: u64,
+                path: P,
+                data: &mut $data_type,
+            ) -> $crate::error::Result<()>
+            where
+                P: AsRef<std::path::Path>,
+            {
+                use $crate::block::BlockProducer as _;
+
+                let producer = $crate::fasta::Producer::with_blocksize(blocksize, path)?;
+                let mut chunks = $crate::fasta::ChunkProducer::from_producer(
+                    self.chunk_length,
+                    self.overlap,
+                    producer,
+                );
+
+                let mut block = None;
+                while let Some(chunk) = chunks.next_chunk()? {
+                    if block != Some(chunk.bases.index()) {
+                        block = Some(chunk.bases.index());
+                        $crate::parser::report(
+                            chunks.producer().progress(),
+                            self.progress.as_ref(),
+                        );
+                    }
+
+                    $chunk(chunk, data);
+                }
+
+                Ok(())
+            }
+        }
+    };
+}
+
 #[cfg(test)]
 mod tests {
-
+    /* project use */
+    #[cfg(any(feature = "fasta", feature = "fastq"))]
+    use crate::error;
     #[cfg(feature = "fasta")]
     use crate::fasta;
     #[cfg(feature = "fastq")]
@@ -105,12 +275,11 @@ mod tests {
         impl_sequential!(
             FastaRecordCount,
             fasta::Producer::with_blocksize,
-            fasta::Reader::new,
+            fasta::Reader,
             u64,
             |_record: fasta::Record, counter: &mut u64| {
                 *counter += 1;
             },
-            fasta::Record,
         );
 
         let mut counter = 0;
@@ -130,14 +299,13 @@ mod tests {
         impl_sequential!(
             FastaNucCount,
             fasta::Producer::with_blocksize,
-            fasta::Reader::new,
+            fasta::Reader,
             [u64; 4],
             |record: fasta::Record, bases: &mut [u64; 4]| {
                 for nuc in record.sequence {
                     bases[(nuc >> 1 & 0b11) as usize] += 1;
                 }
             },
-            fasta::Record,
         );
 
         let mut bases = [0; 4];
@@ -151,18 +319,43 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(feature = "fasta")]
+    #[test]
+    fn kmer_count_fasta_chunk() -> error::Result<()> {
+        fasta_chunk_sequential!(
+            FastaKmerCount,
+            u64,
+            |chunk: fasta::SequenceChunk, counter: &mut u64| {
+                *counter += chunk.bases.data().windows(21).count() as u64;
+            }
+        );
+
+        let mut counter = 0;
+
+        let mut parser = FastaKmerCount::new(64, 20);
+
+        parser.with_blocksize(
+            1_500,
+            crate::tests::generate_fasta(42, 10, 1_000)?,
+            &mut counter,
+        )?;
+
+        assert_eq!(10 * (1_000 - 21 + 1), counter);
+
+        Ok(())
+    }
+
     #[cfg(feature = "fastq")]
     #[test]
     fn record_count_fastq() -> error::Result<()> {
         impl_sequential!(
             FastqRecordCount,
             fastq::Producer::with_blocksize,
-            fastq::Reader::new,
+            fastq::Reader,
             u64,
             |_record: fastq::Record, counter: &mut u64| {
                 *counter += 1;
             },
-            fastq::Record,
         );
 
         let mut counter = 0;
@@ -178,20 +371,55 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(feature = "fastq")]
+    #[test]
+    fn progress() -> error::Result<()> {
+        impl_sequential!(
+            FastqRecordCount,
+            fastq::Producer::with_blocksize,
+            fastq::Reader,
+            u64,
+            |_record: fastq::Record, counter: &mut u64| {
+                *counter += 1;
+            },
+        );
+
+        let reports = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+
+        let mut counter = 0;
+        let mut parser = FastqRecordCount::new();
+        let shared = reports.clone();
+        parser.set_progress(move |progress| shared.lock().unwrap().push(progress));
+
+        let file = crate::tests::generate_fastq(42, 1_000, 150)?;
+        parser.with_blocksize(8192, file.path(), &mut counter)?;
+
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.len(), 39);
+        assert!(reports
+            .windows(2)
+            .all(|pair| pair[0].consumed < pair[1].consumed));
+        assert_eq!(
+            reports.last().map(|progress| progress.fraction()),
+            Some(1.0)
+        );
+
+        Ok(())
+    }
+
     #[cfg(feature = "fastq")]
     #[test]
     fn base_count_fastq() -> error::Result<()> {
         impl_sequential!(
             FastqNucCount,
             fastq::Producer::with_blocksize,
-            fastq::Reader::new,
+            fastq::Reader,
             [u64; 4],
             |record: fastq::Record, bases: &mut [u64; 4]| {
                 for nuc in record.sequence {
                     bases[(nuc >> 1 & 0b11) as usize] += 1;
                 }
             },
-            fastq::Record,
         );
 
         let mut bases = [0; 4];
@@ -204,4 +432,70 @@ mod tests {
 
         Ok(())
     }
+
+    #[cfg(feature = "fastq")]
+    #[test]
+    fn record_count_fastq_files() -> error::Result<()> {
+        fastq_sequential!(
+            FastqRecordCount,
+            Vec<u64>,
+            |record: fastq::Record, counter: &mut Vec<u64>| {
+                counter[record.origin().source_index] += 1;
+            }
+        );
+
+        let files = [
+            crate::tests::generate_fastq(42, 100, 150)?,
+            crate::tests::generate_fastq(43, 1_000, 150)?,
+            crate::tests::generate_fastq(44, 10, 150)?,
+        ];
+        let mut counter = vec![0; 3];
+
+        let mut parser = FastqRecordCount::new();
+
+        parser.parse_files(files.iter().map(|file| file.path()), &mut counter)?;
+
+        assert_eq!(vec![100, 1_000, 10], counter);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "fastq")]
+    #[test]
+    fn lenient_fastq() -> error::Result<()> {
+        impl_sequential!(
+            FastqLenientCount,
+            fastq::Producer::with_blocksize,
+            fastq::Reader,
+            u64,
+            |_record: fastq::Record, counter: &mut u64| {
+                *counter += 1;
+            },
+        );
+
+        let file = crate::tests::write_in_tempfile(
+            b"@0\nACGT\n+\n!!!!\n@1\nACGT\n+\n!!\n@2\nACGT\n+\n!!!!\n",
+        )?;
+
+        let mut counter = 0;
+        let mut parser = FastqLenientCount::new();
+
+        assert!(parser.parse(file.path(), &mut counter).is_ok());
+        assert_eq!(counter, 3);
+
+        counter = 0;
+        parser.set_parse_mode(crate::block::ParseMode::Lenient);
+        parser.parse(file.path(), &mut counter)?;
+
+        assert_eq!(counter, 2);
+        assert_eq!(
+            parser.skipped(),
+            crate::block::Skipped {
+                records: 1,
+                bytes: 13
+            }
+        );
+
+        Ok(())
+    }
 }
@@ -5,12 +5,36 @@
 /// Macro to generate a sharedstate parser
 #[macro_export(local_inner_macros)]
 macro_rules! impl_sharedstate {
-    ($name:ident, $producer:expr, $reader:expr, $data_type:ty, $record:expr,) => {
-        pub struct $name {}
+    ($name:ident, $producer:expr, $reader:ty, $data_type:ty, $record:expr,) => {
+        pub struct $name {
+            progress: Option<$crate::parser::ProgressCallback>,
+            parse_mode: $crate::block::ParseMode,
+            skipped: std::sync::Mutex<$crate::block::Skipped>,
+        }
 
+        #[allow(dead_code)]
         impl $name {
             pub fn new() -> Self {
-                Self {}
+                Self {
+                    progress: None,
+                    parse_mode: $crate::block::ParseMode::default(),
+                    skipped: std::sync::Mutex::new($crate::block::Skipped::default()),
+                }
+            }
+
+            pub fn set_progress<F>(&mut self, callback: F)
+            where
+                F: Fn($crate::block::Progress) + Send + Sync + 'static,
+            {
+                self.progress = Some(Box::new(callback));
+            }
+
+            pub fn set_parse_mode(&mut self, mode: $crate::block::ParseMode) {
+                self.parse_mode = mode;
+            }
+
+            pub fn skipped(&self) -> $crate::block::Skipped {
+                *self.skipped.lock().unwrap()
             }
 
             pub fn parse<P>(&mut self, path: P, data: &$data_type) -> $crate::error::Result<()>
@@ -29,21 +53,83 @@ macro_rules! impl_sharedstate {
             where
                 P: AsRef<std::path::Path>,
             {
-                let producer = $producer(blocksize, path)?;
+                use $crate::block::BlockProducer as _;
+
+                let mut producer = $producer(blocksize, path)?;
+                producer.set_parse_mode(self.parse_mode);
+
+                let blocks = std::iter::from_fn(|| {
+                    let block = producer.next_block().transpose();
+                    $crate::parser::report(producer.progress(), self.progress.as_ref());
+                    block
+                });
+
+                self.blocks(blocks, data)
+            }
+
+            pub fn parse_files<I, P>(
+                &mut self,
+                paths: I,
+                data: &$data_type,
+            ) -> $crate::error::Result<()>
+            where
+                I: IntoIterator<Item = P>,
+                P: AsRef<std::path::Path>,
+            {
+                self.files_with_blocksize($crate::DEFAULT_BLOCKSIZE, paths, data)
+            }
+
+            fn files_with_blocksize<I, P>(
+                &self,
+                blocksize: u64,
+                paths: I,
+                data: &$data_type,
+            ) -> $crate::error::Result<()>
+            where
+                I: IntoIterator<Item = P>,
+                P: AsRef<std::path::Path>,
+            {
+                use $crate::block::BlockProducer as _;
+
+                let mut producer =
+                    $crate::block::MultiProducer::with_constructor(blocksize, paths, $producer);
+                producer.set_parse_mode(self.parse_mode);
+
+                let blocks = std::iter::from_fn(|| {
+                    let block = producer.next_block().transpose();
+                    $crate::parser::report(producer.progress(), self.progress.as_ref());
+                    block
+                });
+
+                self.blocks(blocks, data)
+            }
+
+            fn blocks<I>(&self, blocks: I, data: &$data_type) -> $crate::error::Result<()>
+            where
+                I: Iterator<Item = $crate::error::Result<$crate::block::Block>> + Send,
+            {
+                use $crate::block::RecordReader as _;
+
+                *self.skipped.lock().unwrap() = $crate::block::Skipped::default();
 
-                match producer
+                match blocks
                     .par_bridge()
                     .map(|block| {
-                        let mut reader = $reader(block?);
+                        let mut reader = <$reader>::from_block(block?);
+                        reader.set_parse_mode(self.parse_mode);
                         while let Some(record) = reader.next_record()? {
                             $record(record, data);
                         }
+                        *self.skipped.lock().unwrap() += reader.skipped();
                         Ok(())
                     })
                     .find_any(|x| x.is_err())
                 {
                     Some(e) => e,
-                    None => Ok(()),
+                    None => {
+                        $crate::parser::summarize(self.skipped());
+                        Ok(())
+                    }
                 }
             }
         }
@@ -58,7 +144,7 @@ macro_rules! fasta_sharedstate {
         impl_sharedstate!(
             $name,
             $crate::fasta::Producer::with_blocksize,
-            $crate::fasta::Reader::new,
+            $crate::fasta::Reader,
             $data_type,
             $record,
         );
@@ -73,13 +159,97 @@ macro_rules! fastq_sharedstate {
         impl_sharedstate!(
             $name,
             $crate::fastq::Producer::with_blocksize,
-            $crate::fastq::Reader::new,
+            $crate::fastq::Reader,
             $data_type,
             $record,
         );
     };
 }
 
+#[cfg(feature = "fasta")]
+/// Macro to generate a sharedstate fasta parser over [SequenceChunk](crate::fasta::SequenceChunk) of sequences
+#[macro_export(local_inner_macros)]
+macro_rules! fasta_chunk_sharedstate {
+    ($name:ident, $data_type:ty, $chunk:expr) => {
+        pub struct $name {
+            chunk_length: u64,
+            overlap: u64,
+            progress: Option<$crate::parser::ProgressCallback>,
+        }
+
+        #[allow(dead_code)]
+        impl $name {
+            pub fn new(chunk_length: u64, overlap: u64) -> Self {
+                Self {
+                    chunk_length,
+                    overlap,
+                    progress: None,
+                }
+            }
+
+            pub fn set_progress<F>(&mut self, callback: F)
+            where
+                F: Fn($crate::block::Progress) + Send + Sync + 'static,
+            {
+                self.progress = Some(Box::new(callback));
+            }
+
+            pub fn parse<P>(&mut self, path: P, data: &$data_type) -> $crate::error::Result<()>
+            where
+                P: AsRef<std::path::Path>,
+            {
+                self.with_blocksize($crate::DEFAULT_BLOCKSIZE, path, data)
+            }
+
+            fn with_blocksize<P>(
+                &self,
+                blocksize: u64,
+                path: P,
+                data: &$data_type,
+            ) -> $crate::error::Result<()>
+            where
+                P: AsRef<std::path::Path>,
+            {
+                use $crate::block::BlockProducer as _;
+
+                let producer = $crate::fasta::Producer::with_blocksize(blocksize, path)?;
+                let mut producer = $crate::fasta::ChunkProducer::from_producer(
+                    self.chunk_length,
+                    self.overlap,
+                    producer,
+                );
+
+                let mut block = None;
+                let chunks = std::iter::from_fn(|| {
+                    let chunk = producer.next_chunk().transpose()?;
+                    if let Ok(chunk) = &chunk {
+                        if block != Some(chunk.bases.index()) {
+                            block = Some(chunk.bases.index());
+                            $crate::parser::report(
+                                producer.producer().progress(),
+                                self.progress.as_ref(),
+                            );
+                        }
+                    }
+                    Some(chunk)
+                });
+
+                match chunks
+                    .par_bridge()
+                    .map(|chunk| {
+                        $chunk(chunk?, data);
+                        Ok(())
+                    })
+                    .find_any(|x| x.is_err())
+                {
+                    Some(e) => e,
+                    None => Ok(()),
+                }
+            }
+        }
+    };
+}
+
 #[cfg(test)]
 mod tests {
     /* crate use */
@@ -108,7 +278,10 @@ mod tests {
         let mut parser = FastaRecordCount::new();
 
         parser
-            .parse(crate::tests::generate_fasta(42, 1_000, 150), &counter)
+            .parse(
+                crate::tests::generate_fasta(42, 1_000, 150).unwrap(),
+                &counter,
+            )
             .unwrap();
 
         assert_eq!(1000, counter.into_inner());
@@ -138,7 +311,10 @@ mod tests {
         let mut parser = FastaRecordCount::new();
 
         parser
-            .parse(crate::tests::generate_fasta(42, 1_000, 150), &counter)
+            .parse(
+                crate::tests::generate_fasta(42, 1_000, 150).unwrap(),
+                &counter,
+            )
             .unwrap();
 
         assert_eq!([37378, 37548, 37548, 37526], unsafe {
@@ -146,6 +322,34 @@ mod tests {
         });
     }
 
+    #[cfg(feature = "fasta")]
+    #[test]
+    fn kmer_count_fasta_chunk() {
+        fasta_chunk_sharedstate!(
+            FastaKmerCount,
+            std::sync::atomic::AtomicU64,
+            |chunk: fasta::SequenceChunk, counter: &std::sync::atomic::AtomicU64| {
+                counter.fetch_add(
+                    chunk.bases.data().windows(21).count() as u64,
+                    std::sync::atomic::Ordering::SeqCst,
+                );
+            }
+        );
+
+        let counter = std::sync::atomic::AtomicU64::new(0);
+
+        let mut parser = FastaKmerCount::new(64, 20);
+
+        parser
+            .parse(
+                crate::tests::generate_fasta(42, 10, 1_000).unwrap(),
+                &counter,
+            )
+            .unwrap();
+
+        assert_eq!(10 * (1_000 - 21 + 1), counter.into_inner());
+    }
+
     #[cfg(feature = "fastq")]
     #[test]
     fn record_count_fastq() {
@@ -162,7 +366,39 @@ mod tests {
         let mut parser = FastqRecordCount::new();
 
         parser
-            .parse(crate::tests::generate_fastq(42, 1_000, 150), &counter)
+            .parse(
+                crate::tests::generate_fastq(42, 1_000, 150).unwrap(),
+                &counter,
+            )
+            .unwrap();
+
+        assert_eq!(1000, counter.into_inner());
+    }
+
+    #[cfg(all(feature = "fastq", feature = "bgzf"))]
+    #[test]
+    fn record_count_fastq_bgzf() {
+        fastq_sharedstate!(
+            FastqRecordCount,
+            std::sync::atomic::AtomicU64,
+            |_record: fastq::Record, counter: &std::sync::atomic::AtomicU64| {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            }
+        );
+
+        let counter = std::sync::atomic::AtomicU64::new(0);
+
+        let file = crate::tests::generate_fastq(42, 1_000, 150).unwrap();
+        let data = std::fs::read(file.path()).unwrap();
+        let compressed = crate::tests::write_in_tempfile(
+            &crate::block::bgzf::tests::compress(&data, 65280).unwrap(),
+        )
+        .unwrap();
+
+        let parser = FastqRecordCount::new();
+
+        parser
+            .with_blocksize(8192, compressed.path(), &counter)
             .unwrap();
 
         assert_eq!(1000, counter.into_inner());
@@ -192,11 +428,48 @@ mod tests {
         let mut parser = FastqRecordCount::new();
 
         parser
-            .parse(crate::tests::generate_fastq(42, 1_000, 150), &counter)
+            .parse(
+                crate::tests::generate_fastq(42, 1_000, 150).unwrap(),
+                &counter,
+            )
             .unwrap();
 
         assert_eq!([37301, 37496, 37624, 37579], unsafe {
             std::mem::transmute::<[std::sync::atomic::AtomicU64; 4], [u64; 4]>(counter)
         });
     }
+
+    #[cfg(feature = "fastq")]
+    #[test]
+    fn record_count_fastq_files() {
+        fastq_sharedstate!(
+            FastqRecordCount,
+            [std::sync::atomic::AtomicU64; 3],
+            |record: fastq::Record, counter: &[std::sync::atomic::AtomicU64; 3]| {
+                counter[record.origin().source_index]
+                    .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            }
+        );
+
+        let files = [
+            crate::tests::generate_fastq(42, 100, 150).unwrap(),
+            crate::tests::generate_fastq(43, 1_000, 150).unwrap(),
+            crate::tests::generate_fastq(44, 10, 150).unwrap(),
+        ];
+        let counter = [
+            std::sync::atomic::AtomicU64::new(0),
+            std::sync::atomic::AtomicU64::new(0),
+            std::sync::atomic::AtomicU64::new(0),
+        ];
+
+        let mut parser = FastqRecordCount::new();
+
+        parser
+            .parse_files(files.iter().map(|file| file.path()), &counter)
+            .unwrap();
+
+        assert_eq!([100, 1_000, 10], unsafe {
+            std::mem::transmute::<[std::sync::atomic::AtomicU64; 3], [u64; 3]>(counter)
+        });
+    }
 }
@@ -7,13 +7,10 @@ use bstr::ByteSlice as _;
 
 /* project use */
 use crate::block;
+use crate::block::BlockProducer as _;
 use crate::error;
-use crate::impl_producer;
 use crate::impl_reader;
 
-/* mod declaration */
-pub mod header;
-
 /// Struct that store a VCF record
 pub struct Record<'a> {
     /// Chromosome name
@@ -45,12 +42,14 @@ pub struct Record<'a> {
 
     /// Genotype
     pub genotype: &'a [u8],
+
+    origin: block::Origin<'a>,
 }
 
 impl<'a> Record<'a> {
     /// Build a record from a line
     pub fn from_line(line: &'a [u8]) -> error::Result<Self> {
-        let mut spliter = line.splitn_str(9, "\t");
+        let mut spliter = line.splitn_str(10, "\t");
 
         Ok(Record {
             chromosome: spliter.next().ok_or(error::Error::PartialRecord)?,
@@ -63,33 +62,341 @@ impl<'a> Record<'a> {
             info: spliter.next().ok_or(error::Error::PartialRecord)?,
             format: spliter.next().ok_or(error::Error::PartialRecord)?,
             genotype: spliter.next().ok_or(error::Error::PartialRecord)?,
+            origin: block::Origin::default(),
         })
     }
+
+    /// Get origin of record
+    pub fn origin(&self) -> block::Origin<'a> {
+        self.origin
+    }
+
+    /// Get absolute offset of record in file
+    pub fn file_offset(&self) -> u64 {
+        self.origin.file_offset
+    }
+
+    /// Copy record in an owned record
+    pub fn to_owned(&self) -> OwnedRecord {
+        let mut owned = OwnedRecord::default();
+        self.copy_to(&mut owned);
+
+        owned
+    }
+
+    /// Copy record in an owned record, memory of owned record is reused
+    pub fn copy_to(&self, owned: &mut OwnedRecord) {
+        owned.chromosome.clear();
+        owned.chromosome.extend_from_slice(self.chromosome);
+        owned.position.clear();
+        owned.position.extend_from_slice(self.position);
+        owned.identifiant.clear();
+        owned.identifiant.extend_from_slice(self.identifiant);
+        owned.reference.clear();
+        owned.reference.extend_from_slice(self.reference);
+        owned.alternative.clear();
+        owned.alternative.extend_from_slice(self.alternative);
+        owned.quality.clear();
+        owned.quality.extend_from_slice(self.quality);
+        owned.filter.clear();
+        owned.filter.extend_from_slice(self.filter);
+        owned.info.clear();
+        owned.info.extend_from_slice(self.info);
+        owned.format.clear();
+        owned.format.extend_from_slice(self.format);
+        owned.genotype.clear();
+        owned.genotype.extend_from_slice(self.genotype);
+        owned.file_offset = self.origin.file_offset;
+    }
 }
 
-impl_producer!(Producer, |block: &[u8]| {
-    let mut end = block.len();
+/// Struct that store a VCF record that own its data
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OwnedRecord {
+    /// Chromosome name
+    pub chromosome: Vec<u8>,
 
-    end = block[..end]
-        .rfind_byte(b'\n')
-        .ok_or(error::Error::NoNewLineInBlock)?;
+    /// Position
+    pub position: Vec<u8>,
+
+    /// Identifiant
+    pub identifiant: Vec<u8>,
+
+    /// Reference sequence
+    pub reference: Vec<u8>,
+
+    /// Alternative sequence
+    pub alternative: Vec<u8>,
+
+    /// Quality of variant
+    pub quality: Vec<u8>,
+
+    /// Filter
+    pub filter: Vec<u8>,
 
-    if end < block.len() && block[end] == b'\n' {
-        return Ok((end + 1) as u64);
+    /// Info
+    pub info: Vec<u8>,
+
+    /// Format
+    pub format: Vec<u8>,
+
+    /// Genotype
+    pub genotype: Vec<u8>,
+
+    file_offset: u64,
+}
+
+impl OwnedRecord {
+    /// Get a record that borrow data of owned record
+    pub fn as_ref(&self) -> Record<'_> {
+        Record {
+            chromosome: &self.chromosome,
+            position: &self.position,
+            identifiant: &self.identifiant,
+            reference: &self.reference,
+            alternative: &self.alternative,
+            quality: &self.quality,
+            filter: &self.filter,
+            info: &self.info,
+            format: &self.format,
+            genotype: &self.genotype,
+            origin: block::Origin {
+                file_offset: self.file_offset,
+                ..block::Origin::default()
+            },
+        }
+    }
+
+    /// Get absolute offset of record in file
+    pub fn file_offset(&self) -> u64 {
+        self.file_offset
+    }
+}
+
+/// Vcf block producer
+pub struct Producer {
+    state: block::ProducerState,
+}
+
+impl block::BlockProducer for Producer {
+    fn from_state(state: block::ProducerState) -> Self {
+        Self { state }
     }
 
-    Err(error::Error::NotAVcfFile)
-});
+    fn state(&self) -> &block::ProducerState {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut block::ProducerState {
+        &mut self.state
+    }
+
+    fn correct_block_size(block: &[u8]) -> error::Result<u64> {
+        let mut end = block.len();
+
+        end = block[..end]
+            .rfind_byte(b'\n')
+            .ok_or(error::Error::NoNewLineInBlock)?;
+
+        if end < block.len() && block[end] == b'\n' {
+            return Ok((end + 1) as u64);
+        }
+
+        Err(error::Error::NotAVcfFile)
+    }
+
+    fn find_record_start(data: &[u8], from: usize) -> error::Result<Option<usize>> {
+        Ok(block::line_starts(data, from).find(|pos| data[*pos] != b'#' && data[*pos] != b'\n'))
+    }
+}
+
+impl Iterator for Producer {
+    type Item = error::Result<block::Block>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_block().transpose()
+    }
+}
 
 impl_reader!(
     Reader,
     'a,
-    Record,
-    |block: &'a block::Block, offset: &mut usize| {
-    if *offset == block.len() {
-        Ok(None)
-    } else {
-        Ok(Some(Record::from_line(&block.data()[Self::get_line(block, offset)?])?))
+    Record<'a>,
+    (),
+    |block: &'a block::Block, offset: &mut usize, _mode: block::ParseMode, _options: &()| {
+        Self::skip_header_lines(block, offset);
+
+        if *offset == block.len() {
+            Ok(None)
+        } else {
+            let origin = block.origin_at(*offset);
+
+            let mut record = Record::from_line(Self::next_line(block, offset)?)?;
+            record.origin = origin;
+
+            Ok(Some(record))
+        }
+    },
+    Self::skip_header_lines,
+    |data: &[u8], from: usize| block::line_starts(data, from).next().unwrap_or(data.len())
+);
+
+impl Reader {
+    /// Move offset after empty lines and header lines (begin by `#`)
+    pub fn skip_header_lines(block: &block::Block, offset: &mut usize) {
+        Self::skip_empty_lines(block, offset);
+
+        while block.data().get(*offset) == Some(&b'#') {
+            *offset = memchr::memchr(b'\n', &block.data()[*offset..])
+                .map(|next| *offset + next + 1)
+                .unwrap_or(block.len());
+            Self::skip_empty_lines(block, offset);
+        }
     }
+
+    /// Copy next available record in owned, memory of owned record is reused, return false if block is consumed
+    pub fn next_record_into(&mut self, owned: &mut OwnedRecord) -> error::Result<bool> {
+        match self.next_record()? {
+            Some(record) => {
+                record.copy_to(owned);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
     }
-);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod producer {
+        use super::*;
+
+        #[test]
+        fn with_range() -> error::Result<()> {
+            let file = crate::tests::write_in_tempfile(
+                b"##fileformat=VCFv4.3
+#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\tsample
+1\t10\t.\tA\tT\t.\tPASS\t.\tGT\t0/1
+1\t20\t.\tC\tG\t.\tPASS\t.\tGT\t1/1
+2\t30\t.\tG\tA\t.\tPASS\t.\tGT\t0/1
+",
+            )?;
+
+            let mut producer = Producer::with_range(0, 95, file.path())?;
+            assert_eq!(
+                producer.next_block()?.unwrap().data(),
+                b"1\t10\t.\tA\tT\t.\tPASS\t.\tGT\t0/1\n"
+            );
+            assert!(producer.next_block()?.is_none());
+
+            let mut producer = Producer::with_range(95, 200, file.path())?;
+            assert_eq!(
+                producer.next_block()?.unwrap().data(),
+                b"1\t20\t.\tC\tG\t.\tPASS\t.\tGT\t1/1\n2\t30\t.\tG\tA\t.\tPASS\t.\tGT\t0/1\n"
+            );
+            assert!(producer.next_block()?.is_none());
+
+            Ok(())
+        }
+    }
+
+    mod reader {
+        use super::*;
+
+        #[test]
+        fn normalisation() -> error::Result<()> {
+            let file = crate::tests::write_in_tempfile(
+                b"##fileformat=VCFv4.3\r
+#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\tsample\r
+1\t10\t.\tA\tT\t.\tPASS\t.\tGT\t0/1\r
+\r
+1\t20\t.\tC\tG\t.\tPASS\t.\tGT\t1/1
+
+2\t30\t.\tG\tA\t.\tPASS\t.\tGT\t0/1",
+            )?;
+
+            let mut records = Vec::new();
+            for block in Producer::new(file.path())? {
+                let block = block?;
+                let mut reader = Reader::new(block);
+
+                while let Some(record) = reader.next_record()? {
+                    records.push((
+                        record.position.to_vec(),
+                        record.genotype.to_vec(),
+                        record.file_offset(),
+                    ));
+                }
+            }
+
+            assert_eq!(
+                records,
+                vec![
+                    (b"10".to_vec(), b"0/1".to_vec(), 76),
+                    (b"20".to_vec(), b"1/1".to_vec(), 106),
+                    (b"30".to_vec(), b"0/1".to_vec(), 134),
+                ]
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn lenient() -> error::Result<()> {
+            let data = b"##fileformat=VCFv4.3
+#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\tsample
+1\t10\t.\tA
+1\t20\t.\tC\tG\t.\tPASS\t.\tGT\t1/1
+2\t30
+2\t40\t.\tG\tA\t.\tPASS\t.\tGT\t0/1
+";
+            let block =
+                block::Block::from_buffer(0..data.len(), std::sync::Arc::new(data.to_vec()));
+
+            let mut reader = Reader::new(block.clone());
+            assert!(reader.next_record().is_err());
+
+            let mut reader = Reader::new(block);
+            reader.set_parse_mode(block::ParseMode::Lenient);
+
+            let mut positions = Vec::new();
+            while let Some(record) = reader.next_record()? {
+                positions.push(record.position.to_vec());
+            }
+
+            assert_eq!(positions, vec![b"20".to_vec(), b"40".to_vec()]);
+            assert_eq!(
+                reader.skipped(),
+                block::Skipped {
+                    records: 2,
+                    bytes: 14
+                }
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn owned_record() -> error::Result<()> {
+            let file = crate::tests::write_in_tempfile(
+                b"#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\tsample
+1\t10\t.\tA\tT\t.\tPASS\t.\tGT\t0/1
+",
+            )?;
+
+            let mut reader = Reader::new(Producer::new(file.path())?.next_block()?.unwrap());
+            let mut owned = OwnedRecord::default();
+
+            assert!(reader.next_record_into(&mut owned)?);
+            assert!(!reader.next_record_into(&mut owned)?);
+
+            assert_eq!(owned.position, b"10");
+            assert_eq!(owned.as_ref().genotype, b"0/1");
+            assert_eq!(owned.file_offset(), 53);
+
+            Ok(())
+        }
+    }
+}